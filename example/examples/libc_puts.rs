@@ -1,10 +1,15 @@
+#[cfg(target_os = "android")]
 use std::error::Error;
+#[cfg(target_os = "android")]
 use std::os::raw::*;
+#[cfg(target_os = "android")]
 use android_xdl::Library;
 
+#[cfg(target_os = "android")]
 #[allow(non_camel_case_types)]
 type fn_puts_t = unsafe extern "C" fn(*const c_char) -> c_int;
 
+#[cfg(target_os = "android")]
 fn main() -> Result<(), Box<dyn Error>> {
     let library = Library::open(c"libc.so")?;
     println!("Successfully opened libc, handle: {:p}", unsafe {
@@ -18,3 +23,8 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+#[cfg(not(target_os = "android"))]
+fn main() {
+    eprintln!("test_puts only runs on Android");
+}
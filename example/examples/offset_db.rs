@@ -0,0 +1,42 @@
+//! Generates an offset database entry from a symbolized reference build.
+//!
+//! Usage: `offset_db <library.so> <database.toml|database.json> <symbol>...`
+//!
+//! The entry is merged into the database file, which is created if it does not exist. The
+//! generator only reads ELF files, so it also runs on the host:
+//!
+//! `cargo run -p libc_example --example offset_db --features offsets -- <args>`
+use android_xdl::elf::ElfFile;
+use android_xdl::offsets::{OffsetDatabase, OffsetEntry};
+use std::error::Error;
+use std::path::Path;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let [library, database, symbols @ ..] = args.as_slice() else {
+        return Err(
+            "usage: offset_db <library.so> <database.toml|database.json> <symbol>...".into(),
+        );
+    };
+
+    let file = ElfFile::open(library)?;
+    let entry = OffsetEntry::from_reference(&file, None, symbols.iter().map(String::as_str))?;
+    println!("{} {} ({})", entry.name, entry.build_id, entry.arch);
+    for (name, offset) in &entry.offsets {
+        println!("    {name} = {offset:#x}");
+    }
+
+    let path = Path::new(database);
+    let mut db = if path.exists() {
+        OffsetDatabase::load(path)?
+    } else {
+        OffsetDatabase::new()
+    };
+    db.insert(entry);
+    let text = match path.extension() {
+        Some(ext) if ext.eq_ignore_ascii_case("json") => db.to_json()?,
+        _ => db.to_toml()?,
+    };
+    std::fs::write(path, text)?;
+    Ok(())
+}
@@ -1,13 +1,19 @@
+#[cfg(target_os = "android")]
 use android_xdl::derive::NativeBridge;
+#[cfg(target_os = "android")]
 use android_xdl::wrapper::Container;
+#[cfg(target_os = "android")]
 use android_xdl::{Error, Library};
 use chrono::Local;
 use env_logger::fmt::style::Style;
 use std::io::Write;
+#[cfg(target_os = "android")]
 use std::os::raw::*;
 
+#[cfg(target_os = "android")]
 type Result<T> = std::result::Result<T, Error>;
 
+#[cfg(target_os = "android")]
 #[derive(NativeBridge)]
 #[native(logger)]
 struct LibcApi {
@@ -18,6 +24,7 @@ struct LibcApi {
     non_existent_function: Option<unsafe extern "C" fn() -> c_int>,
 }
 
+#[cfg(target_os = "android")]
 fn example() -> Result<()> {
     let api = Container::<LibcApi>::from(Library::open(c"libc.so")?)?;
 
@@ -36,6 +43,7 @@ fn example() -> Result<()> {
     Ok(())
 }
 
+#[cfg(target_os = "android")]
 fn main() {
     init_logger();
     if let Err(e) = example() {
@@ -43,6 +51,12 @@ fn main() {
     }
 }
 
+#[cfg(not(target_os = "android"))]
+fn main() {
+    init_logger();
+    log::error!("libc_example only runs on Android");
+}
+
 fn init_logger() {
     let env = env_logger::Env::default().default_filter_or("trace");
     env_logger::Builder::from_env(env)
@@ -14,7 +14,6 @@ pub struct Field<'a> {
     pub ident: syn::Ident,
     pub attrs: attr::FieldMetadata,
     pub ty: &'a syn::Type,
-    #[allow(dead_code)]
     pub original: &'a syn::Field,
 }
 
@@ -10,6 +10,7 @@ pub struct Metadata {
     pub implicit: ImplicitMetadata,
     pub symbol: SymbolMetadata,
     pub logger: bool,
+    pub checked: bool,
 }
 
 #[derive(Default)]
@@ -40,6 +41,10 @@ impl Metadata {
                     if let Some(lit) = get_lit_bool(cx, "logger", &meta)? {
                         metadata.logger = lit.value;
                     }
+                } else if meta.path.is_ident("checked") {
+                    if let Some(lit) = get_lit_bool(cx, "checked", &meta)? {
+                        metadata.checked = lit.value;
+                    }
                 } else {
                     let path = meta.path.to_token_stream().to_string().replace(' ', "");
                     return Err(meta.error(format_args!("unknown attribute `{}`", path)));
@@ -113,6 +118,7 @@ pub struct FieldMetadata {
     pub implicit: ImplicitMetadata,
     pub symbols: Vec<SymbolSpec>,
     pub logger: Option<bool>,
+    pub lazy: bool,
 }
 
 pub struct SymbolSpec {
@@ -141,6 +147,10 @@ impl FieldMetadata {
                     if let Some(lit) = get_lit_bool(cx, "logger", &meta)? {
                         metadata.logger = Some(lit.value);
                     }
+                } else if meta.path.is_ident("lazy") {
+                    if let Some(lit) = get_lit_bool(cx, "lazy", &meta)? {
+                        metadata.lazy = lit.value;
+                    }
                 } else {
                     let path = meta.path.to_token_stream().to_string().replace(' ', "");
                     return Err(meta.error(format_args!("unknown attribute `{}`", path)));
@@ -22,6 +22,12 @@ use syn::{DeriveInput, parse_macro_input};
 生成日志相关代码: 成功加载记录 `trace` 级别日志, 失败记录 `warn` 级别日志
 - 默认值: `false`
 
+### `#[native(checked)]`
+加载符号前检查 `.dynsym` 中的符号类型 (`STT_FUNC`/`STT_OBJECT`/`STT_TLS`) 和大小是否与字段类型相符,
+参考 `android_xdl::SymbolKind`
+- 不适用于从调试符号表中加载的符号
+- 默认值: `false`
+
 ## 字段属性
 ### `#[native(implicit)]`
 参考[派生属性](#)
@@ -35,6 +41,22 @@ use syn::{DeriveInput, parse_macro_input};
 - 使用 `"puts"debug` 表示从调试符号表中加载
 - 如果没有指定, 会根据字段名隐式生成默认的符号
 - 如果存在多个符号名称, 将会按顺序优先级加载, 直至加载成功为止
+
+### `#[native(lazy)]`
+延迟加载: 字段类型须为 `LazySymbol<'a, T>`, 符号在第一次调用时才加载并缓存,
+适用于很少使用的函数
+- 生成的包装方法返回 `Result<R, android_xdl::Error>`, 加载失败时返回错误, 下次调用时会重新加载
+- `T` 可以是函数、共享引用或指针, 只能指定一个符号名称
+- 不能与 `checked` 和 `logger` 同时使用, 否则编译报错 (结构体启用 `logger` 时, 可在字段上用 `#[native(logger = false)]` 关闭)
+- 默认值: `false`
+
+## 字段类型
+### `&'a AtomicU32` 等原子类型引用
+除了返回引用的访问器外, 还会生成 `load_xxx(order)` 和 `store_xxx(value, order)` 方法,
+用于在其他线程同时修改该全局变量时进行原子访问
+
+### `&'a mut T` 及原子类型引用
+加载时会检查符号是否位于可写段中 (不在 `.rodata` 或 RELRO 中), 否则返回 `Error::InvalidSymbol`
  */
 #[proc_macro_derive(NativeBridge, attributes(native))]
 pub fn derive_native_bridge(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
@@ -11,11 +11,13 @@ pub fn expand_derive(ast: &DeriveInput) -> syn::Result<TokenStream> {
     };
     let ident = &metadata.ident;
     let (impl_generics, ty_generics, where_clause) = metadata.generics.split_for_impl();
-    let symbols_load_clause = generate_symbols_load_clause(&ctxt, &metadata);
+    let mut requests = Vec::new();
+    let symbols_load_clause = generate_symbols_load_clause(&ctxt, &metadata, &mut requests);
     let symbols_wrapper = generate_symbols_wrapper(&ctxt, &metadata);
     let tokens = quote! {
         impl #impl_generics ::android_xdl::wrapper::Symbols for #ident #ty_generics #where_clause {
             unsafe fn load_from(lib: &::android_xdl::raw::Library) -> ::std::result::Result<Self, ::android_xdl::Error> {
+                let __symbols = lib.resolve_batch(&[#(#requests),*]);
                 Ok(Self { #symbols_load_clause })
             }
         }
@@ -28,18 +30,26 @@ pub fn expand_derive(ast: &DeriveInput) -> syn::Result<TokenStream> {
     Ok(tokens)
 }
 
-fn generate_symbols_load_clause(cx: &Ctxt, meta: &ast::Metadata) -> TokenStream {
+fn generate_symbols_load_clause(
+    cx: &Ctxt,
+    meta: &ast::Metadata,
+    requests: &mut Vec<TokenStream>,
+) -> TokenStream {
     let mut tokens = TokenStream::new();
     for field in &meta.fields {
+        if field.attrs.lazy {
+            tokens.extend(generate_lazy_load_expr(cx, field, meta));
+            continue;
+        }
         match skip_type_group(field.ty) {
             syn::Type::BareFn(_) | syn::Type::Reference(_) | syn::Type::Ptr(_) => {
                 let ident = &field.ident;
-                let expr = generate_symbols_load_expr(field, meta);
+                let expr = generate_symbols_load_expr(field, meta, requests);
                 tokens.extend(quote!(#ident: #expr?,))
             }
             syn::Type::Path(ty) if get_option_inner_type(ty).is_some() => {
                 let ident = &field.ident;
-                let expr = generate_symbols_load_expr(field, meta);
+                let expr = generate_symbols_load_expr(field, meta, requests);
                 tokens.extend(quote!(#ident: #expr.ok(),))
             }
             _ => {
@@ -56,7 +66,100 @@ fn generate_symbols_load_clause(cx: &Ctxt, meta: &ast::Metadata) -> TokenStream
     tokens
 }
 
-fn generate_symbols_load_expr(field: &ast::Field, meta: &ast::Metadata) -> TokenStream {
+/// Generates the initializer of a `#[native(lazy)]` field, which is not part of the batch.
+fn generate_lazy_load_expr(cx: &Ctxt, field: &ast::Field, meta: &ast::Metadata) -> TokenStream {
+    match get_lazy_inner_type(field.ty).map(skip_type_group) {
+        Some(syn::Type::BareFn(_) | syn::Type::Ptr(_)) => {}
+        Some(syn::Type::Reference(ty)) if ty.mutability.is_none() => {}
+        _ => {
+            cx.error_spanned_by(
+                field.ty,
+                format!(
+                    "unsupported type: `{}`, expected `LazySymbol` of function, shared reference, pointer",
+                    field.ty.into_token_stream()
+                ),
+            );
+            return TokenStream::new();
+        }
+    }
+    if field.attrs.logger.unwrap_or(meta.attrs.logger) {
+        cx.error_spanned_by(
+            field.original,
+            "`logger` is not supported on lazy fields, disable it with `#[native(logger = false)]`",
+        );
+    }
+    if meta.attrs.checked {
+        cx.error_spanned_by(
+            field.original,
+            "lazy fields can not be checked, remove `#[native(checked)]` or `#[native(lazy)]`",
+        );
+    }
+    let mut symbols = get_symbol_names(field, meta);
+    if symbols.len() != 1 {
+        cx.error_spanned_by(field.ty, "lazy fields take exactly one symbol name");
+        return TokenStream::new();
+    }
+    let ident = &field.ident;
+    let (symbol, debug) = symbols.remove(0);
+    quote! {
+        #ident: unsafe { ::android_xdl::LazySymbol::from_raw(lib, #symbol, #debug) },
+    }
+}
+
+/// Generates the expression binding a field from the results of `resolve_batch`, adding the
+/// symbols of the field to `requests`.
+fn generate_symbols_load_expr(
+    field: &ast::Field,
+    meta: &ast::Metadata,
+    requests: &mut Vec<TokenStream>,
+) -> TokenStream {
+    let logger = field.attrs.logger.unwrap_or(meta.attrs.logger);
+    let writable = get_writable_reference(field.ty);
+    get_symbol_names(field, meta)
+        .into_iter()
+        .map(|(symbol, debug)| {
+            let index = requests.len();
+            requests.push(match debug {
+                true => quote!(::android_xdl::raw::SymbolRequest::debug(#symbol)),
+                false => {
+                    let gnu = gnu_hash(symbol.as_bytes());
+                    let sysv = sysv_hash(symbol.as_bytes());
+                    quote!(::android_xdl::raw::SymbolRequest::prehashed(#symbol, #gnu, #sysv))
+                }
+            });
+            let ident = match (debug, meta.attrs.checked) {
+                (false, true) => format_ident!("bind_resolved_checked"),
+                _ => format_ident!("bind_resolved"),
+            };
+            let mut expr = quote! {
+                lib.#ident(&__symbols[#index], #symbol)
+            };
+            if let Some(ty) = writable {
+                expr.extend(quote! {
+                    .and_then(|symbol: #ty| {
+                        let address = &*symbol as *const _ as *const ::core::ffi::c_void;
+                        let size = ::core::mem::size_of_val(&*symbol);
+                        lib.check_writable(address, size, #symbol).map(|_| symbol)
+                    })
+                });
+            }
+            if logger {
+                expr.extend(quote! {
+                    .inspect(|symbol| {
+                        ::log::trace!("Symbol `{}` loaded at {:p}", #symbol.to_string_lossy(), *symbol)
+                    })
+                    .inspect_err(|e| ::log::warn!("{}", e))
+                });
+            }
+            expr
+        })
+        .reduce(|acc, expr| quote!(#acc.or_else(|_| #expr)))
+        .unwrap()
+}
+
+/// Returns the names of the symbols of a field, in order of priority, and whether they are
+/// looked up in `.symtab`.
+fn get_symbol_names(field: &ast::Field, meta: &ast::Metadata) -> Vec<(CString, bool)> {
     let symbols = if field.attrs.symbols.is_empty() {
         let implicit = &field.attrs.implicit;
         let ast_implicit = &meta.attrs.implicit;
@@ -90,31 +193,25 @@ fn generate_symbols_load_expr(field: &ast::Field, meta: &ast::Metadata) -> Token
                 }
                 attr::LitString::CString(name) => name.to_owned(),
             };
-            let logger = field.attrs.logger.unwrap_or(meta.attrs.logger);
-            (symbol, spec.debug, logger)
+            (symbol, spec.debug)
         })
-        .map(|(symbol, debug, logger)| {
-            let ident = format_ident!("{}", if debug { "debug_symbol" } else { "symbol" });
-            let mut expr = quote! {
-                lib.#ident(#symbol, None)
-            };
-            if logger {
-                expr.extend(quote! {
-                    .inspect(|symbol| {
-                        ::log::trace!("Symbol `{}` loaded at {:p}", #symbol.to_string_lossy(), *symbol)
-                    })
-                    .inspect_err(|e| ::log::warn!("{}", e))
-                });
-            }
-            expr
-        })
-        .reduce(|acc, expr| quote!(#acc.or_else(|_| #expr)))
-        .unwrap()
+        .collect()
 }
 
 fn generate_symbols_wrapper(cx: &Ctxt, meta: &ast::Metadata) -> TokenStream {
     let mut tokens = TokenStream::new();
     for field in meta.fields.iter() {
+        if field.attrs.lazy {
+            let wrapper = match get_lazy_inner_type(field.ty).map(skip_type_group) {
+                Some(syn::Type::BareFn(ty)) => generate_lazy_function_wrapper(&field.ident, ty),
+                Some(ty @ syn::Type::Reference(_)) => {
+                    Some(generate_lazy_reference_wrapper(&field.ident, ty))
+                }
+                _ => None,
+            };
+            tokens.extend(wrapper);
+            continue;
+        }
         let wrapper = match skip_type_group(field.ty) {
             syn::Type::BareFn(ty) => generate_function_wrapper(&field.ident, ty),
             syn::Type::Reference(ty) => Some(generate_reference_wrapper(&field.ident, ty)),
@@ -177,12 +274,27 @@ fn generate_reference_wrapper(ident: &syn::Ident, ref_ty: &syn::TypeReference) -
             }
         }
     });
+    let atomic_acc = get_atomic_value_type(ty).map(|value_ty| {
+        let load_ident = format_ident!("load_{ident}");
+        let store_ident = format_ident!("store_{ident}");
+        quote! {
+            #[inline]
+            pub fn #load_ident(&self, order: ::core::sync::atomic::Ordering) -> #value_ty {
+                self.#ident.load(order)
+            }
+            #[inline]
+            pub fn #store_ident(&self, value: #value_ty, order: ::core::sync::atomic::Ordering) {
+                self.#ident.store(value, order)
+            }
+        }
+    });
     quote! {
         #[inline]
         pub fn #ident(&self) -> & #ty {
             self.#ident
         }
         #mut_acc
+        #atomic_acc
     }
 }
 
@@ -240,6 +352,58 @@ fn generate_optional_reference_wrapper(
     }
 }
 
+fn generate_lazy_function_wrapper(
+    ident: &syn::Ident,
+    fn_ty: &syn::TypeBareFn,
+) -> Option<TokenStream> {
+    match fn_ty.variadic {
+        None => {
+            let return_type = match &fn_ty.output {
+                syn::ReturnType::Default => quote!(()),
+                syn::ReturnType::Type(_, ty) => quote!(#ty),
+            };
+            let unsafety = fn_ty.unsafety;
+            let args_name = get_bera_fn_arg_idents(fn_ty);
+            let args_type = fn_ty.inputs.iter().map(|arg| &arg.ty);
+            Some(quote! {
+                #[inline]
+                pub #unsafety fn #ident(&self, #(#args_name: #args_type),*)
+                    -> ::core::result::Result<#return_type, ::android_xdl::Error>
+                {
+                    let f = self.#ident.get()?;
+                    Ok(#unsafety { f(#(#args_name),*) })
+                }
+            })
+        }
+        Some(_) => None,
+    }
+}
+
+fn generate_lazy_reference_wrapper(ident: &syn::Ident, ty: &syn::Type) -> TokenStream {
+    quote! {
+        #[inline]
+        pub fn #ident(&self) -> ::core::result::Result<#ty, ::android_xdl::Error> {
+            self.#ident.get()
+        }
+    }
+}
+
+/// The `DT_GNU_HASH` hash of a symbol name, see `android_xdl::elf::gnu_hash`.
+fn gnu_hash(name: &[u8]) -> u32 {
+    name.iter().fold(5381u32, |hash, &c| {
+        hash.wrapping_mul(33).wrapping_add(c as u32)
+    })
+}
+
+/// The `DT_HASH` hash of a symbol name, see `android_xdl::elf::sysv_hash`.
+fn sysv_hash(name: &[u8]) -> u32 {
+    name.iter().fold(0u32, |hash, &c| {
+        let hash = (hash << 4).wrapping_add(c as u32);
+        let high = hash & 0xf000_0000;
+        (hash ^ (high >> 24)) & !high
+    })
+}
+
 fn get_bera_fn_arg_idents(fn_ty: &syn::TypeBareFn) -> Vec<syn::Ident> {
     let args = fn_ty.inputs.iter().enumerate();
     let args = args.map(|(i, arg)| match &arg.name {
@@ -249,6 +413,54 @@ fn get_bera_fn_arg_idents(fn_ty: &syn::TypeBareFn) -> Vec<syn::Ident> {
     args.collect()
 }
 
+/// Returns the reference type of a `&mut T` or atomic reference field, possibly in an
+/// `Option`, which the wrappers write through.
+fn get_writable_reference(ty: &syn::Type) -> Option<&syn::Type> {
+    let ty = match skip_type_group(ty) {
+        syn::Type::Path(ty) => skip_type_group(get_option_inner_type(ty)?),
+        ty => ty,
+    };
+    match ty {
+        syn::Type::Reference(reference)
+            if reference.mutability.is_some()
+                || get_atomic_value_type(&reference.elem).is_some() =>
+        {
+            Some(ty)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the value type of an atomic type, e.g. `u32` for `AtomicU32`.
+fn get_atomic_value_type(ty: &syn::Type) -> Option<TokenStream> {
+    let syn::Type::Path(ty) = skip_type_group(ty) else {
+        return None;
+    };
+    let segment = ty.path.segments.last()?;
+    let value_ty = match segment.ident.to_string().as_str() {
+        "AtomicBool" => quote!(bool),
+        "AtomicI8" => quote!(i8),
+        "AtomicU8" => quote!(u8),
+        "AtomicI16" => quote!(i16),
+        "AtomicU16" => quote!(u16),
+        "AtomicI32" => quote!(i32),
+        "AtomicU32" => quote!(u32),
+        "AtomicI64" => quote!(i64),
+        "AtomicU64" => quote!(u64),
+        "AtomicIsize" => quote!(isize),
+        "AtomicUsize" => quote!(usize),
+        "AtomicPtr" => match &segment.arguments {
+            syn::PathArguments::AngleBracketed(generics) => match generics.args.first()? {
+                syn::GenericArgument::Type(ty) => quote!(*mut #ty),
+                _ => return None,
+            },
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(value_ty)
+}
+
 fn skip_type_group(ty: &syn::Type) -> &syn::Type {
     match ty {
         syn::Type::Group(group) => skip_type_group(&group.elem),
@@ -274,3 +486,65 @@ fn get_option_inner_type(ty: &syn::TypePath) -> Option<&syn::Type> {
             _ => None,
         })
 }
+
+/// Returns `T` of a `LazySymbol<'a, T>` field.
+fn get_lazy_inner_type(ty: &syn::Type) -> Option<&syn::Type> {
+    let syn::Type::Path(ty) = skip_type_group(ty) else {
+        return None;
+    };
+    let segment = ty
+        .path
+        .segments
+        .last()
+        .filter(|segment| segment.ident == "LazySymbol")?;
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(generics) => match generics.args.last()? {
+            syn::GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{gnu_hash, sysv_hash};
+
+    const NAMES: &[&[u8]] = &[
+        b"",
+        b"a",
+        b"puts",
+        b"printf",
+        b"il2cpp_init",
+        b"__emutls_get_address",
+        b"_ZN7android14AndroidRuntime8startRegEP7_JNIEnv",
+        b"_ZNSt6__ndk112basic_stringIcNS_11char_traitsIcEENS_9allocatorIcEEE6appendEPKc",
+        b"\xff\x80\xfe\x7f",
+        b"\xe4\xb8\xad\xe6\x96\x87_symbol",
+    ];
+
+    /// The hashes baked into `SymbolRequest::prehashed` must match the ones the library computes.
+    #[test]
+    fn hashes_match_the_library() {
+        for name in NAMES {
+            assert_eq!(
+                gnu_hash(name),
+                android_xdl::elf::gnu_hash(name),
+                "{name:x?}"
+            );
+            assert_eq!(
+                sysv_hash(name),
+                android_xdl::elf::sysv_hash(name),
+                "{name:x?}"
+            );
+        }
+    }
+
+    #[test]
+    fn reference_values() {
+        assert_eq!(gnu_hash(b""), 5381);
+        assert_eq!(sysv_hash(b""), 0);
+        assert_eq!(gnu_hash(b"printf"), 0x156b2bb8);
+        assert_eq!(sysv_hash(b"printf"), 0x077905a6);
+    }
+}
@@ -0,0 +1,142 @@
+//! Measures the symbol lookup paths of [`Library`] against `libc.so`, with and without the
+//! symbol cache.
+//!
+//! The library only works on Android: run it on a device, e.g. with
+//! `cargo bench -p android_xdl --target aarch64-linux-android` and a runner that pushes the
+//! binary with `adb`. Pass a number to change the iteration count.
+
+#[cfg(target_os = "android")]
+fn main() {
+    use android_xdl::raw::{Library as RowLibrary, SymbolRequest};
+    use android_xdl::{Library, SymbolHash};
+    use std::ffi::CStr;
+    use std::hint::black_box;
+    use std::os::raw::c_void;
+    use std::time::Instant;
+
+    const SYMBOLS: [&CStr; 8] = [
+        c"puts",
+        c"malloc",
+        c"free",
+        c"memcpy",
+        c"strlen",
+        c"open",
+        c"close",
+        c"pthread_create",
+    ];
+    const DEBUG_SYMBOLS: [&CStr; 2] = [c"__libc_init", c"__libc_preinit"];
+    const HASHES: [SymbolHash; 8] = [
+        SymbolHash::of(SYMBOLS[0]),
+        SymbolHash::of(SYMBOLS[1]),
+        SymbolHash::of(SYMBOLS[2]),
+        SymbolHash::of(SYMBOLS[3]),
+        SymbolHash::of(SYMBOLS[4]),
+        SymbolHash::of(SYMBOLS[5]),
+        SymbolHash::of(SYMBOLS[6]),
+        SymbolHash::of(SYMBOLS[7]),
+    ];
+
+    /// Prints the mean time of one lookup, over `iterations` runs of `f`, which looks up
+    /// `symbols` symbols.
+    fn bench(name: &str, iterations: u32, symbols: usize, mut f: impl FnMut()) {
+        for _ in 0..iterations.div_ceil(10) {
+            f();
+        }
+        let start = Instant::now();
+        for _ in 0..iterations {
+            f();
+        }
+        let per_lookup = start.elapsed() / (iterations * symbols as u32);
+        println!("{name:<32} {per_lookup:>10.2?} per symbol");
+    }
+
+    let iterations = std::env::args()
+        .skip(1)
+        .find_map(|arg| arg.parse().ok())
+        .unwrap_or(10_000);
+    let lib = Library::open(c"libc.so").expect("libc.so is loaded");
+    let cached = Library::open(c"libc.so").unwrap().with_cache();
+
+    bench("xdl_sym", iterations, SYMBOLS.len(), || {
+        for name in SYMBOLS {
+            black_box(unsafe { RowLibrary::symbol::<*const c_void>(&lib, name, None) }.ok());
+        }
+    });
+    bench("symbol", iterations, SYMBOLS.len(), || {
+        for name in SYMBOLS {
+            black_box(lib.symbol::<*const c_void>(name).ok());
+        }
+    });
+    bench("symbol_prehashed", iterations, SYMBOLS.len(), || {
+        for (name, hash) in SYMBOLS.into_iter().zip(HASHES) {
+            black_box(lib.symbol_prehashed::<*const c_void>(name, hash).ok());
+        }
+    });
+    let requests = SYMBOLS.map(SymbolRequest::new);
+    bench("resolve_batch", iterations, SYMBOLS.len(), || {
+        black_box(lib.resolve_batch(&requests));
+    });
+    let requests: Vec<_> = SYMBOLS
+        .into_iter()
+        .zip(HASHES)
+        .map(|(name, hash)| SymbolRequest::prehashed(name, hash.gnu, hash.sysv))
+        .collect();
+    bench("resolve_batch prehashed", iterations, SYMBOLS.len(), || {
+        black_box(lib.resolve_batch(&requests));
+    });
+
+    bench("symbol (cached)", iterations, SYMBOLS.len(), || {
+        for name in SYMBOLS {
+            black_box(cached.symbol::<*const c_void>(name).ok());
+        }
+    });
+    bench(
+        "symbol_prehashed (cached)",
+        iterations,
+        SYMBOLS.len(),
+        || {
+            for (name, hash) in SYMBOLS.into_iter().zip(HASHES) {
+                black_box(cached.symbol_prehashed::<*const c_void>(name, hash).ok());
+            }
+        },
+    );
+    bench("resolve_batch (cached)", iterations, SYMBOLS.len(), || {
+        black_box(cached.resolve_batch(&requests));
+    });
+
+    // `xdl_dsym` reads the library from disk on every call: fewer iterations are enough.
+    let debug_iterations = iterations.div_ceil(100);
+    bench("xdl_dsym", debug_iterations, DEBUG_SYMBOLS.len(), || {
+        for name in DEBUG_SYMBOLS {
+            black_box(unsafe { RowLibrary::debug_symbol::<*const c_void>(&lib, name, None) }.ok());
+        }
+    });
+    bench("debug_symbol", iterations, DEBUG_SYMBOLS.len(), || {
+        for name in DEBUG_SYMBOLS {
+            black_box(lib.debug_symbol::<*const c_void>(name).ok());
+        }
+    });
+    bench(
+        "debug_symbol (cached)",
+        iterations,
+        DEBUG_SYMBOLS.len(),
+        || {
+            for name in DEBUG_SYMBOLS {
+                black_box(cached.debug_symbol::<*const c_void>(name).ok());
+            }
+        },
+    );
+
+    if let Some(cache) = cached.cache() {
+        let stats = cache.stats();
+        println!(
+            "cache: {} hits, {} misses, {} entries",
+            stats.hits, stats.misses, stats.entries
+        );
+    }
+}
+
+#[cfg(not(target_os = "android"))]
+fn main() {
+    eprintln!("the benchmarks need an Android target");
+}
@@ -0,0 +1,127 @@
+/*!
+Access to native libraries stored uncompressed inside an APK.
+
+Apps built with `extractNativeLibs=false` load their libraries straight from the APK, and the
+linker reports them as `/data/app/.../base.apk!/lib/arm64-v8a/libfoo.so`. Such a path can not be
+opened as a file, so the ZIP entry is located through the central directory instead.
+*/
+use crate::Error;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Separates the APK path from the entry name in a module path.
+pub(crate) const SEPARATOR: &str = "!/";
+
+const EOCD_SIGNATURE: u32 = 0x06054b50;
+const CENTRAL_SIGNATURE: u32 = 0x02014b50;
+const LOCAL_SIGNATURE: u32 = 0x04034b50;
+const EOCD_SIZE: usize = 22;
+const MAX_COMMENT: usize = 0xffff;
+const METHOD_STORED: u16 = 0;
+
+/// Splits `base.apk!/lib/arm64-v8a/libfoo.so` into the APK path and the entry name.
+pub(crate) fn split_path(path: &str) -> Option<(&str, &str)> {
+    path.split_once(SEPARATOR)
+}
+
+/// Location of a stored (uncompressed) entry within the APK file.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct StoredEntry {
+    pub offset: u64,
+    pub size: u64,
+}
+
+/// Locates the stored entry `entry` of the APK at `apk`.
+pub(crate) fn find_stored_entry(apk: &Path, entry: &str) -> Result<StoredEntry> {
+    let error = |msg: String| Error::InvalidElf(format!("`{}`: {}", apk.display(), msg));
+    let io_error = |err: std::io::Error| error(err.to_string());
+    let mut file = File::open(apk).map_err(io_error)?;
+    let len = file.seek(SeekFrom::End(0)).map_err(io_error)?;
+
+    // The end of central directory record is followed by a comment of up to 64 KiB.
+    let tail_len = len.min((EOCD_SIZE + MAX_COMMENT) as u64);
+    let tail = read_at(&mut file, len - tail_len, tail_len as usize).map_err(io_error)?;
+    let eocd = (0..=tail.len().saturating_sub(EOCD_SIZE))
+        .rev()
+        .find(|&at| u32_at(&tail, at) == Some(EOCD_SIGNATURE))
+        .ok_or_else(|| error("not a ZIP file".to_string()))?;
+    let (Some(count), Some(cd_size), Some(cd_offset)) = (
+        u16_at(&tail, eocd + 10),
+        u32_at(&tail, eocd + 12),
+        u32_at(&tail, eocd + 16),
+    ) else {
+        return Err(error("truncated end of central directory".to_string()));
+    };
+    if cd_offset as u64 + cd_size as u64 > len {
+        return Err(error("central directory out of bounds".to_string()));
+    }
+
+    let directory = read_at(&mut file, cd_offset as u64, cd_size as usize).map_err(io_error)?;
+    let mut at = 0;
+    for _ in 0..count {
+        let header = (|| {
+            (u32_at(&directory, at)? == CENTRAL_SIGNATURE).then_some(())?;
+            let method = u16_at(&directory, at + 10)?;
+            let size = u32_at(&directory, at + 24)?;
+            let name_len = u16_at(&directory, at + 28)? as usize;
+            let extra_len = u16_at(&directory, at + 30)? as usize;
+            let comment_len = u16_at(&directory, at + 32)? as usize;
+            let local_offset = u32_at(&directory, at + 42)?;
+            let name = directory.get(at + 46..at + 46 + name_len)?;
+            Some((
+                method,
+                size,
+                local_offset,
+                name,
+                46 + name_len + extra_len + comment_len,
+            ))
+        })()
+        .ok_or_else(|| error("malformed central directory".to_string()))?;
+        let (method, size, local_offset, name, header_len) = header;
+        at += header_len;
+        if name != entry.as_bytes() {
+            continue;
+        }
+        if method != METHOD_STORED {
+            return Err(error(format!("entry `{}` is compressed", entry)));
+        }
+        let local = read_at(&mut file, local_offset as u64, 30).map_err(io_error)?;
+        let (Some(LOCAL_SIGNATURE), Some(name_len), Some(extra_len)) =
+            (u32_at(&local, 0), u16_at(&local, 26), u16_at(&local, 28))
+        else {
+            return Err(error(format!("malformed local header of `{}`", entry)));
+        };
+        return Ok(StoredEntry {
+            offset: local_offset as u64 + 30 + name_len as u64 + extra_len as u64,
+            size: size as u64,
+        });
+    }
+    Err(error(format!("no entry `{}`", entry)))
+}
+
+/// Reads the content of the stored entry `entry` of the APK at `apk`.
+pub(crate) fn read_stored_entry(apk: &Path, entry: &str) -> Result<Vec<u8>> {
+    let stored = find_stored_entry(apk, entry)?;
+    let mut file = File::open(apk)
+        .map_err(|err| Error::InvalidElf(format!("`{}`: {}", apk.display(), err)))?;
+    read_at(&mut file, stored.offset, stored.size as usize)
+        .map_err(|err| Error::InvalidElf(format!("`{}!/{}`: {}", apk.display(), entry, err)))
+}
+
+fn read_at(file: &mut File, offset: u64, len: usize) -> std::io::Result<Vec<u8>> {
+    let mut buf = vec![0; len];
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
@@ -0,0 +1,294 @@
+use super::{Class, Image, PT_DYNAMIC};
+use crate::Error;
+use std::ffi::CStr;
+
+pub(crate) const DT_NULL: u64 = 0;
+pub(crate) const DT_NEEDED: u64 = 1;
+pub(crate) const DT_PLTRELSZ: u64 = 2;
+pub(crate) const DT_HASH: u64 = 4;
+pub(crate) const DT_STRTAB: u64 = 5;
+pub(crate) const DT_SYMTAB: u64 = 6;
+pub(crate) const DT_RELA: u64 = 7;
+pub(crate) const DT_RELASZ: u64 = 8;
+pub(crate) const DT_STRSZ: u64 = 10;
+pub(crate) const DT_SONAME: u64 = 14;
+pub(crate) const DT_RPATH: u64 = 15;
+pub(crate) const DT_REL: u64 = 17;
+pub(crate) const DT_RELSZ: u64 = 18;
+pub(crate) const DT_PLTREL: u64 = 20;
+pub(crate) const DT_JMPREL: u64 = 23;
+pub(crate) const DT_RUNPATH: u64 = 29;
+pub(crate) const DT_RELRSZ: u64 = 35;
+pub(crate) const DT_RELR: u64 = 36;
+pub(crate) const DT_ANDROID_REL: u64 = 0x6000000f;
+pub(crate) const DT_ANDROID_RELSZ: u64 = 0x60000010;
+pub(crate) const DT_ANDROID_RELA: u64 = 0x60000011;
+pub(crate) const DT_ANDROID_RELASZ: u64 = 0x60000012;
+pub(crate) const DT_GNU_HASH: u64 = 0x6ffffef5;
+pub(crate) const DT_ANDROID_RELR: u64 = 0x6fffe000;
+pub(crate) const DT_ANDROID_RELRSZ: u64 = 0x6fffe001;
+
+pub(crate) const SHN_UNDEF: u16 = 0;
+
+pub(crate) const STB_LOCAL: u8 = 0;
+
+pub(crate) const STT_NOTYPE: u8 = 0;
+pub(crate) const STT_OBJECT: u8 = 1;
+pub(crate) const STT_FUNC: u8 = 2;
+pub(crate) const STT_COMMON: u8 = 5;
+pub(crate) const STT_TLS: u8 = 6;
+pub(crate) const STT_GNU_IFUNC: u8 = 10;
+
+/// The parts of the dynamic section the crate is interested in.
+///
+/// All addresses are virtual addresses (not yet relocated by the load bias).
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Dynamic {
+    pub strtab: Option<u64>,
+    pub strsz: u64,
+    pub symtab: Option<u64>,
+    pub hash: Option<u64>,
+    pub gnu_hash: Option<u64>,
+    pub rel: Option<(u64, u64)>,
+    pub rela: Option<(u64, u64)>,
+    pub relr: Option<(u64, u64)>,
+    pub android_rel: Option<(u64, u64)>,
+    pub android_rela: Option<(u64, u64)>,
+    pub jmprel: Option<u64>,
+    pub pltrelsz: u64,
+    pub pltrel: u64,
+    pub needed: Vec<u64>,
+    pub soname: Option<u64>,
+    pub rpath: Option<u64>,
+    pub runpath: Option<u64>,
+}
+
+/// The dynamic linking dependencies declared by a module.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Dependencies {
+    /// `DT_SONAME`
+    pub soname: Option<String>,
+    /// `DT_NEEDED` entries, in declaration (and load) order.
+    pub needed: Vec<String>,
+    /// `DT_RUNPATH`, split at `:`.
+    pub runpath: Vec<String>,
+    /// `DT_RPATH`, split at `:`. Ignored by bionic, listed for completeness.
+    pub rpath: Vec<String>,
+}
+
+/// A symbol read from the symbol table of an [`ElfFile`](super::ElfFile).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElfSymbol {
+    /// Name of the symbol.
+    pub name: String,
+    /// Value of the symbol: its virtual address for a defined function or object.
+    pub value: u64,
+    /// Size of the symbol, in bytes.
+    pub size: u64,
+    /// `false` for an undefined (imported) symbol.
+    pub defined: bool,
+    info: u8,
+}
+
+impl ElfSymbol {
+    pub(crate) fn new(name: String, sym: &Sym) -> Self {
+        Self {
+            name,
+            value: sym.st_value,
+            size: sym.st_size,
+            defined: !sym.is_undefined(),
+            info: sym.st_info,
+        }
+    }
+
+    /// Returns `true` for a function (`STT_FUNC`).
+    pub fn is_function(&self) -> bool {
+        self.info & 0xf == STT_FUNC
+    }
+
+    /// Returns `true` for a data object (`STT_OBJECT` or `STT_COMMON`).
+    pub fn is_object(&self) -> bool {
+        matches!(self.info & 0xf, STT_OBJECT | STT_COMMON)
+    }
+
+    /// Returns `true` for a thread-local variable (`STT_TLS`).
+    pub fn is_tls(&self) -> bool {
+        self.info & 0xf == STT_TLS
+    }
+
+    /// Returns `true` for an indirect function (`STT_GNU_IFUNC`).
+    pub fn is_ifunc(&self) -> bool {
+        self.info & 0xf == STT_GNU_IFUNC
+    }
+
+    /// Returns `true` for a symbol without a type (`STT_NOTYPE`), e.g. defined in assembly.
+    pub fn is_untyped(&self) -> bool {
+        self.info & 0xf == STT_NOTYPE
+    }
+
+    /// Returns `true` for a symbol with `STB_LOCAL` binding.
+    pub fn is_local(&self) -> bool {
+        self.info >> 4 == STB_LOCAL
+    }
+}
+
+/// An entry of `.dynsym` (or `.symtab`), widened to 64 bits.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Sym {
+    pub st_name: u32,
+    pub st_info: u8,
+    pub st_shndx: u16,
+    pub st_value: u64,
+    pub st_size: u64,
+}
+
+impl Sym {
+    pub(crate) fn size_of(class: Class) -> u64 {
+        match class {
+            Class::Elf32 => 16,
+            Class::Elf64 => 24,
+        }
+    }
+
+    /// Parses an entry from its raw bytes, which must be [`Sym::size_of`] long.
+    pub(crate) fn parse(class: Class, bytes: &[u8]) -> Self {
+        let u16_at = |at: usize| u16::from_le_bytes([bytes[at], bytes[at + 1]]);
+        let u32_at = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+        let u64_at = |at: usize| u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap());
+        match class {
+            Class::Elf32 => Self {
+                st_name: u32_at(0),
+                st_value: u32_at(4) as u64,
+                st_size: u32_at(8) as u64,
+                st_info: bytes[12],
+                st_shndx: u16_at(14),
+            },
+            Class::Elf64 => Self {
+                st_name: u32_at(0),
+                st_info: bytes[4],
+                st_shndx: u16_at(6),
+                st_value: u64_at(8),
+                st_size: u64_at(16),
+            },
+        }
+    }
+
+    pub(crate) fn kind(&self) -> u8 {
+        self.st_info & 0xf
+    }
+
+    pub(crate) fn binding(&self) -> u8 {
+        self.st_info >> 4
+    }
+
+    pub(crate) fn is_undefined(&self) -> bool {
+        self.st_shndx == SHN_UNDEF
+    }
+}
+
+impl<'a> Image<'a> {
+    /// Parses the `PT_DYNAMIC` segment. Returns `None` if there is none.
+    pub(crate) fn dynamic(&self) -> Option<Dynamic> {
+        let segment = self.segments().iter().find(|s| s.p_type == PT_DYNAMIC)?;
+        let word = self.class().word_size();
+        let mut dynamic = Dynamic::default();
+        let (mut relsz, mut relasz, mut relrsz) = (0, 0, 0);
+        let (mut android_relsz, mut android_relasz) = (0, 0);
+        for i in 0..segment.p_memsz / (word * 2) {
+            let entry = segment.p_vaddr + i * word * 2;
+            let tag = self.read_word(entry)?;
+            let val = self.read_word(entry + word)?;
+            match tag {
+                DT_NULL => break,
+                DT_NEEDED => dynamic.needed.push(val),
+                DT_SONAME => dynamic.soname = Some(val),
+                DT_RPATH => dynamic.rpath = Some(val),
+                DT_RUNPATH => dynamic.runpath = Some(val),
+                DT_STRTAB => dynamic.strtab = Some(self.dyn_ptr(val)),
+                DT_STRSZ => dynamic.strsz = val,
+                DT_SYMTAB => dynamic.symtab = Some(self.dyn_ptr(val)),
+                DT_HASH => dynamic.hash = Some(self.dyn_ptr(val)),
+                DT_GNU_HASH => dynamic.gnu_hash = Some(self.dyn_ptr(val)),
+                DT_REL => dynamic.rel = Some((self.dyn_ptr(val), 0)),
+                DT_RELSZ => relsz = val,
+                DT_RELA => dynamic.rela = Some((self.dyn_ptr(val), 0)),
+                DT_RELASZ => relasz = val,
+                DT_RELR | DT_ANDROID_RELR => dynamic.relr = Some((self.dyn_ptr(val), 0)),
+                DT_RELRSZ | DT_ANDROID_RELRSZ => relrsz = val,
+                DT_ANDROID_REL => dynamic.android_rel = Some((self.dyn_ptr(val), 0)),
+                DT_ANDROID_RELSZ => android_relsz = val,
+                DT_ANDROID_RELA => dynamic.android_rela = Some((self.dyn_ptr(val), 0)),
+                DT_ANDROID_RELASZ => android_relasz = val,
+                DT_JMPREL => dynamic.jmprel = Some(self.dyn_ptr(val)),
+                DT_PLTRELSZ => dynamic.pltrelsz = val,
+                DT_PLTREL => dynamic.pltrel = val,
+                _ => {}
+            }
+        }
+        let tables = [
+            (&mut dynamic.rel, relsz),
+            (&mut dynamic.rela, relasz),
+            (&mut dynamic.relr, relrsz),
+            (&mut dynamic.android_rel, android_relsz),
+            (&mut dynamic.android_rela, android_relasz),
+        ];
+        for (table, table_size) in tables {
+            if let Some((_, size)) = table {
+                *size = table_size;
+            }
+        }
+        Some(dynamic)
+    }
+
+    /// Like [`dynamic`](Self::dynamic), but fails with [`Error::InvalidElf`].
+    pub(crate) fn require_dynamic(&self) -> Result<Dynamic, Error> {
+        self.dynamic()
+            .ok_or_else(|| Error::InvalidElf("missing or unreadable dynamic section".to_string()))
+    }
+
+    /// Reads `DT_SONAME`, `DT_NEEDED`, `DT_RUNPATH` and `DT_RPATH`.
+    pub(crate) fn dependencies(&self) -> Result<Dependencies, Error> {
+        let dynamic = self.require_dynamic()?;
+        let string = |offset: u64| {
+            self.dynstr(&dynamic, offset)
+                .map(|s| s.to_string_lossy().into_owned())
+                .ok_or_else(|| Error::InvalidElf(format!("bad .dynstr offset {:#x}", offset)))
+        };
+        let paths = |offset: Option<u64>| -> Result<Vec<String>, Error> {
+            Ok(match offset {
+                Some(offset) => string(offset)?
+                    .split(':')
+                    .filter(|path| !path.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+                None => Vec::new(),
+            })
+        };
+        Ok(Dependencies {
+            soname: dynamic.soname.map(string).transpose()?,
+            needed: dynamic
+                .needed
+                .iter()
+                .map(|&offset| string(offset))
+                .collect::<Result<_, _>>()?,
+            runpath: paths(dynamic.runpath)?,
+            rpath: paths(dynamic.rpath)?,
+        })
+    }
+
+    /// Reads a string from `.dynstr`.
+    pub(crate) fn dynstr(&self, dynamic: &Dynamic, offset: u64) -> Option<&'a CStr> {
+        if dynamic.strsz != 0 && offset >= dynamic.strsz {
+            return None;
+        }
+        self.read_cstr(dynamic.strtab?.checked_add(offset)?)
+    }
+
+    /// Reads the entry `index` of `.dynsym`.
+    pub(crate) fn dynsym(&self, dynamic: &Dynamic, index: u64) -> Option<Sym> {
+        let size = Sym::size_of(self.class());
+        let vaddr = dynamic.symtab?.checked_add(index.checked_mul(size)?)?;
+        self.bytes(vaddr, size)
+            .map(|bytes| Sym::parse(self.class(), bytes))
+    }
+}
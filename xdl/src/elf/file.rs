@@ -0,0 +1,361 @@
+use super::{
+    AndroidIdent, BuildId, Class, Dependencies, ElfSymbol, Image, Relocations, Segment, Source, Sym,
+};
+use crate::Error;
+use std::path::Path;
+
+type Result<T> = std::result::Result<T, Error>;
+
+/**
+An ELF file read from disk (or from memory), independently of the linker.
+
+The same decoders used for loaded libraries work on it, which makes it possible to inspect a
+library that is not loaded, or one built for another architecture.
+
+# Example
+
+```no_run
+use android_xdl::elf::ElfFile;
+
+let file = ElfFile::open("/system/lib64/libc.so").unwrap();
+for reloc in file.relocations().unwrap() {
+    println!("{:#x} {:?} {:?}", reloc.offset, reloc.kind, reloc.symbol);
+}
+```
+*/
+#[derive(Debug, Clone)]
+pub struct ElfFile {
+    data: Vec<u8>,
+    class: Class,
+    machine: u16,
+    segments: Vec<Segment>,
+    sections: Vec<Section>,
+    /// Index of the section holding the section names (`e_shstrndx`).
+    shstrndx: u16,
+}
+
+const SHT_SYMTAB: u32 = 2;
+const SHT_DYNSYM: u32 = 11;
+
+/// A section header, widened to 64 bits.
+#[derive(Debug, Clone, Copy)]
+struct Section {
+    sh_name: u32,
+    sh_type: u32,
+    sh_offset: u64,
+    sh_size: u64,
+    sh_link: u32,
+    sh_entsize: u64,
+}
+
+impl ElfFile {
+    /// Reads and parses the ELF file at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let data = std::fs::read(path).map_err(|err| {
+            Error::InvalidElf(format!("cannot read `{}`: {}", path.display(), err))
+        })?;
+        Self::parse(data)
+    }
+
+    /// Reads and parses a library stored uncompressed in an APK, e.g. the entry
+    /// `lib/arm64-v8a/libfoo.so` of `base.apk`.
+    pub fn open_in_apk<P: AsRef<Path>>(apk: P, entry: &str) -> Result<Self> {
+        Self::parse(crate::apk::read_stored_entry(apk.as_ref(), entry)?)
+    }
+
+    /// Parses the bytes of an ELF file.
+    pub fn parse(data: Vec<u8>) -> Result<Self> {
+        let header = Header::parse(&data)?;
+        let reader = Reader(&data);
+        let segments = (0..header.phnum as u64)
+            .map(|i| {
+                let at = header.phoff.checked_add(i * header.phentsize as u64)?;
+                reader.segment(header.class, at)
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| Error::InvalidElf("truncated program header table".to_string()))?;
+        // Section headers are optional at run time: a stripped or truncated table is ignored.
+        let (sections, shstrndx) = reader.sections(header.class).unwrap_or_default();
+        Ok(Self {
+            class: header.class,
+            machine: header.machine,
+            data,
+            segments,
+            sections,
+            shstrndx,
+        })
+    }
+
+    /// Returns `true` for an `ELFCLASS64` file.
+    pub fn is_64bit(&self) -> bool {
+        self.class == Class::Elf64
+    }
+
+    /// Target architecture (`e_machine`), e.g. [`EM_AARCH64`](super::EM_AARCH64).
+    pub fn machine(&self) -> u16 {
+        self.machine
+    }
+
+    /// The raw bytes of the file.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Decodes all relocation tables, see [`Library::relocations`](crate::Library::relocations).
+    pub fn relocations(&self) -> Result<Relocations> {
+        self.image().relocations()
+    }
+
+    /// Reads the declared dependencies, see [`Library::dependencies`](crate::Library::dependencies).
+    pub fn dependencies(&self) -> Result<Dependencies> {
+        self.image().dependencies()
+    }
+
+    /// Reads the `NT_GNU_BUILD_ID` note, see [`Library::build_id`](crate::Library::build_id).
+    pub fn build_id(&self) -> Option<BuildId> {
+        self.image().build_id()
+    }
+
+    /// Reads the `.note.android.ident` note, see
+    /// [`Library::android_ident`](crate::Library::android_ident).
+    pub fn android_ident(&self) -> Option<AndroidIdent> {
+        self.image().android_ident()
+    }
+
+    /// Returns `true` if the file has a `.symtab` section (it was not stripped).
+    pub fn has_symtab(&self) -> bool {
+        self.sections.iter().any(|s| s.sh_type == SHT_SYMTAB)
+    }
+
+    /// Reads the symbols of `.symtab`. Empty if the file was stripped.
+    pub fn symtab(&self) -> Vec<ElfSymbol> {
+        self.section_symbols(SHT_SYMTAB)
+    }
+
+    /// Reads the symbols of `.dynsym`.
+    pub fn dynsym(&self) -> Vec<ElfSymbol> {
+        self.section_symbols(SHT_DYNSYM)
+    }
+
+    /// Looks up a defined symbol by name, in `.symtab` first and then in `.dynsym`.
+    pub fn find_symbol(&self, name: &str) -> Option<ElfSymbol> {
+        [SHT_SYMTAB, SHT_DYNSYM].into_iter().find_map(|sh_type| {
+            self.section_symbols(sh_type)
+                .into_iter()
+                .find(|sym| sym.defined && sym.name == name)
+        })
+    }
+
+    /// Returns the compressed MiniDebugInfo of the file (`.gnu_debugdata`): an xz stream
+    /// holding an ELF file whose `.symtab` lists the local functions stripped from this one.
+    pub fn gnu_debugdata(&self) -> Option<&[u8]> {
+        self.section_by_name(b".gnu_debugdata")
+    }
+
+    fn section_by_name(&self, name: &[u8]) -> Option<&[u8]> {
+        let names = self.section_data(self.sections.get(self.shstrndx as usize)?)?;
+        let section = self.sections.iter().find(|section| {
+            names
+                .get(section.sh_name as usize..)
+                .and_then(|s| std::ffi::CStr::from_bytes_until_nul(s).ok())
+                .is_some_and(|s| s.to_bytes() == name)
+        })?;
+        self.section_data(section)
+    }
+
+    fn section_symbols(&self, sh_type: u32) -> Vec<ElfSymbol> {
+        let size = Sym::size_of(self.class);
+        let Some(section) = self.sections.iter().find(|s| s.sh_type == sh_type) else {
+            return Vec::new();
+        };
+        let Some(strtab) = self.sections.get(section.sh_link as usize) else {
+            return Vec::new();
+        };
+        let (Some(symbols), Some(strings)) =
+            (self.section_data(section), self.section_data(strtab))
+        else {
+            return Vec::new();
+        };
+        let entsize = if section.sh_entsize >= size {
+            section.sh_entsize
+        } else {
+            size
+        };
+        symbols
+            .chunks(entsize as usize)
+            .filter(|chunk| chunk.len() as u64 >= size)
+            .skip(1) // The null symbol.
+            .map(|chunk| {
+                let sym = Sym::parse(self.class, chunk);
+                let name = strings
+                    .get(sym.st_name as usize..)
+                    .and_then(|s| std::ffi::CStr::from_bytes_until_nul(s).ok())
+                    .map(|s| s.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                ElfSymbol::new(name, &sym)
+            })
+            .collect()
+    }
+
+    fn section_data(&self, section: &Section) -> Option<&[u8]> {
+        let start = usize::try_from(section.sh_offset).ok()?;
+        let len = usize::try_from(section.sh_size).ok()?;
+        self.data.get(start..start.checked_add(len)?)
+    }
+
+    pub(crate) fn image(&self) -> Image<'_> {
+        Image {
+            class: self.class,
+            machine: self.machine,
+            source: Source::File(&self.data),
+            segments: self.segments.clone(),
+        }
+    }
+}
+
+/// The fields of the ELF header used by the crate.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Header {
+    pub class: Class,
+    pub machine: u16,
+    pub e_type: u16,
+    pub phoff: u64,
+    pub phentsize: u16,
+    pub phnum: u16,
+}
+
+impl Header {
+    pub(crate) const ET_EXEC: u16 = 2;
+
+    /// Parses and validates the ELF header at the start of `data`.
+    pub(crate) fn parse(data: &[u8]) -> Result<Self> {
+        let invalid = |msg: &str| Error::InvalidElf(msg.to_string());
+        if data.get(..4) != Some(b"\x7fELF") {
+            return Err(invalid("bad magic"));
+        }
+        let class = match data.get(4) {
+            Some(1) => Class::Elf32,
+            Some(2) => Class::Elf64,
+            _ => return Err(invalid("unknown ELF class")),
+        };
+        if data.get(5) != Some(&1) {
+            return Err(invalid("only little-endian files are supported"));
+        }
+        let reader = Reader(data);
+        let fields = match class {
+            Class::Elf32 => (
+                reader.u32(28).map(u64::from),
+                reader.u16(42),
+                reader.u16(44),
+            ),
+            Class::Elf64 => (reader.u64(32), reader.u16(54), reader.u16(56)),
+        };
+        let (Some(e_type), Some(machine), (Some(phoff), Some(phentsize), Some(phnum))) =
+            (reader.u16(16), reader.u16(18), fields)
+        else {
+            return Err(invalid("truncated header"));
+        };
+        Ok(Self {
+            class,
+            machine,
+            e_type,
+            phoff,
+            phentsize,
+            phnum,
+        })
+    }
+}
+
+/// Little-endian field reader for the file headers.
+pub(super) struct Reader<'a>(pub(super) &'a [u8]);
+
+impl Reader<'_> {
+    pub(super) fn u16(&self, at: u64) -> Option<u16> {
+        self.array(at).map(u16::from_le_bytes)
+    }
+
+    pub(super) fn u32(&self, at: u64) -> Option<u32> {
+        self.array(at).map(u32::from_le_bytes)
+    }
+
+    pub(super) fn u64(&self, at: u64) -> Option<u64> {
+        self.array(at).map(u64::from_le_bytes)
+    }
+
+    pub(super) fn word(&self, class: Class, at: u64) -> Option<u64> {
+        match class {
+            Class::Elf32 => self.u32(at).map(u64::from),
+            Class::Elf64 => self.u64(at),
+        }
+    }
+
+    fn array<const N: usize>(&self, at: u64) -> Option<[u8; N]> {
+        let at = usize::try_from(at).ok()?;
+        self.0.get(at..at.checked_add(N)?)?.try_into().ok()
+    }
+
+    /// Reads the section header table and the index of `.shstrtab` (`e_shstrndx`).
+    fn sections(&self, class: Class) -> Option<(Vec<Section>, u16)> {
+        let (shoff, shentsize, shnum, shstrndx) = match class {
+            Class::Elf32 => (
+                self.u32(32)? as u64,
+                self.u16(46)?,
+                self.u16(48)?,
+                self.u16(50)?,
+            ),
+            Class::Elf64 => (self.u64(40)?, self.u16(58)?, self.u16(60)?, self.u16(62)?),
+        };
+        if shoff == 0 {
+            return None;
+        }
+        let sections = (0..shnum as u64)
+            .map(|i| self.section(class, shoff.checked_add(i * shentsize as u64)?))
+            .collect::<Option<_>>()?;
+        Some((sections, shstrndx))
+    }
+
+    fn section(&self, class: Class, at: u64) -> Option<Section> {
+        Some(match class {
+            Class::Elf32 => Section {
+                sh_name: self.u32(at)?,
+                sh_type: self.u32(at + 4)?,
+                sh_offset: self.u32(at + 16)? as u64,
+                sh_size: self.u32(at + 20)? as u64,
+                sh_link: self.u32(at + 24)?,
+                sh_entsize: self.u32(at + 36)? as u64,
+            },
+            Class::Elf64 => Section {
+                sh_name: self.u32(at)?,
+                sh_type: self.u32(at + 4)?,
+                sh_offset: self.u64(at + 24)?,
+                sh_size: self.u64(at + 32)?,
+                sh_link: self.u32(at + 40)?,
+                sh_entsize: self.u64(at + 56)?,
+            },
+        })
+    }
+
+    fn segment(&self, class: Class, at: u64) -> Option<Segment> {
+        Some(match class {
+            Class::Elf32 => Segment {
+                p_type: self.u32(at)?,
+                p_offset: self.u32(at + 4)? as u64,
+                p_vaddr: self.u32(at + 8)? as u64,
+                p_filesz: self.u32(at + 16)? as u64,
+                p_memsz: self.u32(at + 20)? as u64,
+                p_flags: self.u32(at + 24)?,
+                p_align: self.u32(at + 28)? as u64,
+            },
+            Class::Elf64 => Segment {
+                p_type: self.u32(at)?,
+                p_flags: self.u32(at + 4)?,
+                p_offset: self.u64(at + 8)?,
+                p_vaddr: self.u64(at + 16)?,
+                p_filesz: self.u64(at + 32)?,
+                p_memsz: self.u64(at + 40)?,
+                p_align: self.u64(at + 48)?,
+            },
+        })
+    }
+}
@@ -0,0 +1,164 @@
+use super::{Dynamic, Image, STB_LOCAL, STT_FUNC, STT_OBJECT, Sym};
+use std::ffi::CStr;
+
+/// The `DT_GNU_HASH` hash of a symbol name.
+///
+/// A `const fn`, so that hashes of names known at compile time cost nothing at run time.
+pub const fn gnu_hash(name: &[u8]) -> u32 {
+    let mut hash = 5381u32;
+    let mut i = 0;
+    while i < name.len() {
+        hash = hash.wrapping_mul(33).wrapping_add(name[i] as u32);
+        i += 1;
+    }
+    hash
+}
+
+/// The `DT_HASH` (System V) hash of a symbol name.
+pub const fn sysv_hash(name: &[u8]) -> u32 {
+    let mut hash = 0u32;
+    let mut i = 0;
+    while i < name.len() {
+        hash = (hash << 4).wrapping_add(name[i] as u32);
+        let high = hash & 0xf000_0000;
+        hash = (hash ^ (high >> 24)) & !high;
+        i += 1;
+    }
+    hash
+}
+
+impl<'a> Image<'a> {
+    /// Looks up a defined symbol of `.dynsym` through `DT_GNU_HASH`, or `DT_HASH`.
+    pub(crate) fn lookup_dynsym(&self, dynamic: &Dynamic, name: &CStr) -> Option<Sym> {
+        let bytes = name.to_bytes();
+        self.lookup_dynsym_hashed(dynamic, name, gnu_hash(bytes), sysv_hash(bytes))
+    }
+
+    /// Like [`lookup_dynsym`](Self::lookup_dynsym), with hashes computed by the caller.
+    pub(crate) fn lookup_dynsym_hashed(
+        &self,
+        dynamic: &Dynamic,
+        name: &CStr,
+        gnu: u32,
+        sysv: u32,
+    ) -> Option<Sym> {
+        match (dynamic.gnu_hash, dynamic.hash) {
+            (Some(table), _) => self.gnu_lookup(dynamic, table, name, gnu),
+            (None, Some(table)) => self.sysv_lookup(dynamic, table, name, sysv),
+            (None, None) => None,
+        }
+    }
+
+    fn gnu_lookup(&self, dynamic: &Dynamic, table: u64, name: &CStr, hash: u32) -> Option<Sym> {
+        let nbuckets = self.read_u32(table)?;
+        let symoffset = self.read_u32(table + 4)?;
+        let bloom_size = self.read_u32(table + 8)?;
+        let bloom_shift = self.read_u32(table + 12)?;
+        if nbuckets == 0 {
+            return None;
+        }
+
+        // Reject most missing names with the bloom filter.
+        let word = self.class().word_size();
+        let bits = (word * 8) as u32;
+        let bloom = table + 16;
+        let index = (hash / bits) % bloom_size.max(1);
+        let mask = self.read_word(bloom + index as u64 * word)?;
+        let bit1 = hash % bits;
+        let bit2 = (hash >> bloom_shift.min(31)) % bits;
+        if (mask >> bit1) & (mask >> bit2) & 1 == 0 {
+            return None;
+        }
+
+        let buckets = bloom + bloom_size as u64 * word;
+        let chains = buckets + nbuckets as u64 * 4;
+        let mut index = self.read_u32(buckets + (hash % nbuckets) as u64 * 4)?;
+        if index < symoffset {
+            return None;
+        }
+        loop {
+            let chain = self.read_u32(chains + (index - symoffset) as u64 * 4)?;
+            if (chain | 1) == (hash | 1)
+                && let Some(sym) = self.matching_dynsym(dynamic, index, name)
+            {
+                return Some(sym);
+            }
+            if chain & 1 != 0 {
+                return None;
+            }
+            index += 1;
+        }
+    }
+
+    fn sysv_lookup(&self, dynamic: &Dynamic, table: u64, name: &CStr, hash: u32) -> Option<Sym> {
+        let nbucket = self.read_u32(table)?;
+        let nchain = self.read_u32(table + 4)?;
+        if nbucket == 0 {
+            return None;
+        }
+        let buckets = table + 8;
+        let chains = buckets + nbucket as u64 * 4;
+        let mut index = self.read_u32(buckets + (hash % nbucket) as u64 * 4)?;
+        // Bound the walk by the chain length in case the table is corrupted.
+        for _ in 0..nchain {
+            if index == 0 || index >= nchain {
+                return None;
+            }
+            if let Some(sym) = self.matching_dynsym(dynamic, index, name) {
+                return Some(sym);
+            }
+            index = self.read_u32(chains + index as u64 * 4)?;
+        }
+        None
+    }
+
+    /// Returns the defined functions and objects exported by `.dynsym`, with their names, in
+    /// table order.
+    pub(crate) fn exported_dynsyms<'d>(
+        &'d self,
+        dynamic: &'d Dynamic,
+    ) -> impl Iterator<Item = (&'a CStr, Sym)> + 'd {
+        let count = self.dynsym_count(dynamic).unwrap_or(0);
+        (1..count).filter_map(move |index| {
+            let sym = self.dynsym(dynamic, index)?;
+            let exported = !sym.is_undefined()
+                && sym.st_value != 0
+                && sym.binding() != STB_LOCAL
+                && matches!(sym.kind(), STT_FUNC | STT_OBJECT);
+            if !exported {
+                return None;
+            }
+            Some((self.dynstr(dynamic, sym.st_name as u64)?, sym))
+        })
+    }
+
+    /// Number of entries of `.dynsym`, read from the hash table.
+    fn dynsym_count(&self, dynamic: &Dynamic) -> Option<u64> {
+        if let Some(table) = dynamic.hash {
+            return self.read_u32(table + 4).map(u64::from);
+        }
+        let table = dynamic.gnu_hash?;
+        let nbuckets = self.read_u32(table)?;
+        let symoffset = self.read_u32(table + 4)?;
+        let bloom_size = self.read_u32(table + 8)?;
+        let buckets = table + 16 + bloom_size as u64 * self.class().word_size();
+        let chains = buckets + nbuckets as u64 * 4;
+        let mut last = (0..nbuckets as u64)
+            .filter_map(|i| self.read_u32(buckets + i * 4))
+            .max()?;
+        if last < symoffset {
+            return Some(symoffset as u64);
+        }
+        // The chain of the last bucket ends with the last symbol.
+        while self.read_u32(chains + (last - symoffset) as u64 * 4)? & 1 == 0 {
+            last = last.checked_add(1)?;
+        }
+        Some(last as u64 + 1)
+    }
+
+    /// Returns the entry `index` of `.dynsym` if it is a definition of `name`.
+    fn matching_dynsym(&self, dynamic: &Dynamic, index: u32, name: &CStr) -> Option<Sym> {
+        let sym = self.dynsym(dynamic, index as u64)?;
+        (!sym.is_undefined() && self.dynstr(dynamic, sym.st_name as u64)? == name).then_some(sym)
+    }
+}
@@ -0,0 +1,299 @@
+/*!
+Decoding of the ELF structures of a module: the dynamic section (including its
+[`Dependencies`]), `.dynsym`, the relocation tables and the notes identifying the build
+([`BuildId`], [`AndroidIdent`]).
+
+The decoders work on libraries loaded in the current process (see
+[`Library::relocations`](crate::Library::relocations)) as well as on ELF files that are not loaded
+at all ([`ElfFile`]), for any of the four Android ABIs.
+
+Every read is bounds-checked against the `PT_LOAD` segments of the module, so a malformed dynamic
+section can not make the decoders read outside of it.
+*/
+mod dynamic;
+mod file;
+mod hash;
+mod note;
+mod reloc;
+#[cfg(target_os = "android")]
+mod symtab;
+
+pub(crate) use dynamic::*;
+pub use dynamic::{Dependencies, ElfSymbol};
+pub use file::ElfFile;
+pub use hash::{gnu_hash, sysv_hash};
+pub(crate) use file::Header;
+pub use note::{AndroidIdent, BuildId};
+pub(crate) use reloc::RawRelocation;
+pub use reloc::{Relocation, RelocationKind, RelocationSymbol, RelocationTable, Relocations};
+#[cfg(target_os = "android")]
+pub(crate) use symtab::DebugSymbols;
+
+use crate::Error;
+use std::ffi::CStr;
+
+#[cfg(target_pointer_width = "64")]
+pub(crate) type Phdr = libc::Elf64_Phdr;
+#[cfg(target_pointer_width = "32")]
+pub(crate) type Phdr = libc::Elf32_Phdr;
+
+type Result<T> = std::result::Result<T, Error>;
+
+pub(crate) const PT_LOAD: u32 = 1;
+pub(crate) const PT_DYNAMIC: u32 = 2;
+pub(crate) const PT_NOTE: u32 = 4;
+pub(crate) const PT_PHDR: u32 = 6;
+pub(crate) const PT_GNU_RELRO: u32 = 0x6474e552;
+
+pub(crate) const PF_X: u32 = 1;
+pub(crate) const PF_W: u32 = 2;
+pub(crate) const PF_R: u32 = 4;
+
+/// `e_machine` of x86 (`x86`).
+pub const EM_386: u16 = 3;
+/// `e_machine` of 32-bit ARM (`armeabi-v7a`).
+pub const EM_ARM: u16 = 40;
+/// `e_machine` of x86-64 (`x86_64`).
+pub const EM_X86_64: u16 = 62;
+/// `e_machine` of AArch64 (`arm64-v8a`).
+pub const EM_AARCH64: u16 = 183;
+
+#[cfg(target_arch = "x86")]
+pub(crate) const EM_NATIVE: u16 = EM_386;
+#[cfg(target_arch = "arm")]
+pub(crate) const EM_NATIVE: u16 = EM_ARM;
+#[cfg(target_arch = "x86_64")]
+pub(crate) const EM_NATIVE: u16 = EM_X86_64;
+#[cfg(target_arch = "aarch64")]
+pub(crate) const EM_NATIVE: u16 = EM_AARCH64;
+
+/// ELF file class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Class {
+    Elf32,
+    Elf64,
+}
+
+impl Class {
+    #[cfg(target_pointer_width = "64")]
+    pub(crate) const NATIVE: Class = Class::Elf64;
+    #[cfg(target_pointer_width = "32")]
+    pub(crate) const NATIVE: Class = Class::Elf32;
+
+    /// Size of an address, in bytes.
+    pub(crate) fn word_size(self) -> u64 {
+        match self {
+            Class::Elf32 => 4,
+            Class::Elf64 => 8,
+        }
+    }
+}
+
+/// A program header, widened to 64 bits.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Segment {
+    pub p_type: u32,
+    pub p_flags: u32,
+    pub p_offset: u64,
+    pub p_vaddr: u64,
+    pub p_filesz: u64,
+    pub p_memsz: u64,
+    pub p_align: u64,
+}
+
+impl Segment {
+    #[allow(clippy::unnecessary_cast)] // Phdr fields are 32-bit on 32-bit targets.
+    pub(crate) fn from_phdr(phdr: &Phdr) -> Self {
+        Self {
+            p_type: phdr.p_type,
+            p_flags: phdr.p_flags,
+            p_offset: phdr.p_offset as u64,
+            p_vaddr: phdr.p_vaddr as u64,
+            p_filesz: phdr.p_filesz as u64,
+            p_memsz: phdr.p_memsz as u64,
+            p_align: phdr.p_align as u64,
+        }
+    }
+
+    fn contains(&self, vaddr: u64) -> bool {
+        vaddr >= self.p_vaddr && vaddr - self.p_vaddr < self.p_memsz
+    }
+}
+
+enum Source<'a> {
+    /// The module is mapped at `bias` in the current process.
+    Memory { bias: usize },
+    /// The bytes of an ELF file.
+    File(&'a [u8]),
+}
+
+/// A view of the dynamic structures of an ELF image.
+pub(crate) struct Image<'a> {
+    class: Class,
+    machine: u16,
+    source: Source<'a>,
+    segments: Vec<Segment>,
+}
+
+impl std::fmt::Debug for Image<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Image")
+            .field("class", &self.class)
+            .field("machine", &self.machine)
+            .field("bias", &self.bias())
+            .field("segments", &self.segments)
+            .finish()
+    }
+}
+
+impl Image<'static> {
+    /// Creates an image of a module mapped in the current process.
+    ///
+    /// # Safety
+    /// The module must stay loaded for as long as the image is used.
+    pub(crate) unsafe fn from_memory(bias: usize, phdrs: &[Phdr]) -> Self {
+        Self {
+            class: Class::NATIVE,
+            machine: EM_NATIVE,
+            source: Source::Memory { bias },
+            segments: phdrs.iter().map(Segment::from_phdr).collect(),
+        }
+    }
+
+    /// Creates an image of a module opened with xDL.
+    ///
+    /// # Safety
+    /// The library must stay open for as long as the image is used.
+    #[cfg(target_os = "android")]
+    pub(crate) unsafe fn from_library(lib: &crate::raw::Library) -> Result<Self> {
+        unsafe {
+            let info = lib.info()?;
+            if info.dlpi_phdr.is_null() {
+                return Err(Error::InvalidElf(
+                    "the library has no program headers".to_string(),
+                ));
+            }
+            let phdrs = std::slice::from_raw_parts(info.dlpi_phdr, info.dlpi_phnum);
+            Ok(Self::from_memory(info.dli_fbase as usize, phdrs))
+        }
+    }
+}
+
+impl<'a> Image<'a> {
+    pub(crate) fn class(&self) -> Class {
+        self.class
+    }
+
+    pub(crate) fn machine(&self) -> u16 {
+        self.machine
+    }
+
+    /// Load bias of a mapped module, `None` for a file.
+    pub(crate) fn bias(&self) -> Option<usize> {
+        match self.source {
+            Source::Memory { bias } => Some(bias),
+            Source::File(_) => None,
+        }
+    }
+
+    pub(crate) fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Returns the `PT_LOAD` segment containing `vaddr`.
+    pub(crate) fn load_segment(&self, vaddr: u64) -> Option<&Segment> {
+        self.segments
+            .iter()
+            .find(|segment| segment.p_type == PT_LOAD && segment.contains(vaddr))
+    }
+
+    /// Returns `true` if `[vaddr, vaddr + len)` lies within a writable `PT_LOAD` segment and
+    /// outside of `PT_GNU_RELRO`, which the linker makes read-only after relocation.
+    pub(crate) fn is_writable(&self, vaddr: u64, len: u64) -> bool {
+        let Some(end) = vaddr.checked_add(len.max(1) - 1) else {
+            return false;
+        };
+        let writable = self.load_segment(vaddr).is_some_and(|segment| {
+            segment.p_flags & PF_W != 0 && segment.contains(end)
+        });
+        writable
+            && !self.segments.iter().any(|segment| {
+                segment.p_type == PT_GNU_RELRO
+                    && vaddr < segment.p_vaddr.saturating_add(segment.p_memsz)
+                    && end >= segment.p_vaddr
+            })
+    }
+
+    /// Returns `len` bytes at the virtual address `vaddr`, if they lie within one segment.
+    pub(crate) fn bytes(&self, vaddr: u64, len: u64) -> Option<&'a [u8]> {
+        let segment = self.load_segment(vaddr)?;
+        let rel = vaddr - segment.p_vaddr;
+        match self.source {
+            Source::Memory { bias } => {
+                if len > segment.p_memsz - rel {
+                    return None;
+                }
+                let ptr = bias.wrapping_add(vaddr as usize) as *const u8;
+                Some(unsafe { std::slice::from_raw_parts(ptr, len as usize) })
+            }
+            Source::File(data) => {
+                if rel.checked_add(len)? > segment.p_filesz {
+                    return None;
+                }
+                let start = usize::try_from(segment.p_offset.checked_add(rel)?).ok()?;
+                data.get(start..start.checked_add(len as usize)?)
+            }
+        }
+    }
+
+    /// Returns the bytes from `vaddr` up to the end of its segment.
+    pub(crate) fn bytes_to_end(&self, vaddr: u64) -> Option<&'a [u8]> {
+        let segment = self.load_segment(vaddr)?;
+        let end = match self.source {
+            Source::Memory { .. } => segment.p_memsz,
+            Source::File(_) => segment.p_filesz,
+        };
+        self.bytes(vaddr, end.checked_sub(vaddr - segment.p_vaddr)?)
+    }
+
+    pub(crate) fn read_u32(&self, vaddr: u64) -> Option<u32> {
+        self.bytes(vaddr, 4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    pub(crate) fn read_u64(&self, vaddr: u64) -> Option<u64> {
+        self.bytes(vaddr, 8)
+            .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    /// Reads an address-sized value.
+    pub(crate) fn read_word(&self, vaddr: u64) -> Option<u64> {
+        match self.class {
+            Class::Elf32 => self.read_u32(vaddr).map(u64::from),
+            Class::Elf64 => self.read_u64(vaddr),
+        }
+    }
+
+    /// Reads a NUL-terminated string, which must end within the segment.
+    pub(crate) fn read_cstr(&self, vaddr: u64) -> Option<&'a CStr> {
+        CStr::from_bytes_until_nul(self.bytes_to_end(vaddr)?).ok()
+    }
+
+    /// Converts a pointer from the dynamic section to a virtual address.
+    ///
+    /// Bionic leaves these pointers untouched, but other loaders (glibc) relocate them in
+    /// place, so both forms are accepted for a mapped module.
+    pub(crate) fn dyn_ptr(&self, ptr: u64) -> u64 {
+        match self.source {
+            Source::Memory { bias } if bias != 0 && self.load_segment(ptr).is_none() => {
+                let vaddr = ptr.wrapping_sub(bias as u64);
+                if self.load_segment(vaddr).is_some() {
+                    vaddr
+                } else {
+                    ptr
+                }
+            }
+            _ => ptr,
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use super::{Image, PT_NOTE};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+const NT_GNU_BUILD_ID: u32 = 3;
+const NT_ANDROID_TYPE_IDENT: u32 = 1;
+
+/// The `NT_GNU_BUILD_ID` of a module: a hash identifying one exact build.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BuildId(Vec<u8>);
+
+impl BuildId {
+    /// Creates a build ID from its raw bytes.
+    pub fn new(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+
+    /// The raw bytes of the build ID.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Lowercase hexadecimal representation, as printed by `readelf -n` or `file`.
+    pub fn to_hex(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl Display for BuildId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+    }
+}
+
+impl FromStr for BuildId {
+    type Err = crate::Error;
+
+    /// Parses a hexadecimal build ID.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || crate::Error::InvalidElf(format!("`{}` is not a hexadecimal build ID", s));
+        if !s.len().is_multiple_of(2) || !s.is_ascii() {
+            return Err(invalid());
+        }
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(|_| invalid()))
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+/// The `.note.android.ident` note written by the NDK toolchain.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AndroidIdent {
+    /// The target API level (`minSdkVersion`) the module was built for.
+    pub api_level: u32,
+    /// NDK version, e.g. `r26b`. Only recorded since NDK r14.
+    pub ndk_version: Option<String>,
+    /// NDK build number, e.g. `10909125`. Only recorded since NDK r14.
+    pub ndk_build_number: Option<String>,
+}
+
+impl AndroidIdent {
+    fn parse(desc: &[u8]) -> Option<Self> {
+        let api_level = u32::from_le_bytes(desc.get(..4)?.try_into().ok()?);
+        let string = |range: std::ops::Range<usize>| {
+            let bytes = desc.get(range)?;
+            let len = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+            (len > 0).then(|| String::from_utf8_lossy(&bytes[..len]).into_owned())
+        };
+        Some(Self {
+            api_level,
+            ndk_version: string(4..68),
+            ndk_build_number: string(68..132),
+        })
+    }
+}
+
+/// An entry of a `PT_NOTE` segment.
+struct Note<'a> {
+    name: &'a [u8],
+    n_type: u32,
+    desc: &'a [u8],
+}
+
+impl<'a> Image<'a> {
+    /// Returns the `NT_GNU_BUILD_ID` note, if the module has one.
+    pub(crate) fn build_id(&self) -> Option<BuildId> {
+        self.find_note(b"GNU", NT_GNU_BUILD_ID)
+            .map(|desc| BuildId(desc.to_vec()))
+    }
+
+    /// Returns the `.note.android.ident` note, if the module has one.
+    pub(crate) fn android_ident(&self) -> Option<AndroidIdent> {
+        self.find_note(b"Android", NT_ANDROID_TYPE_IDENT)
+            .and_then(AndroidIdent::parse)
+    }
+
+    fn find_note(&self, name: &[u8], n_type: u32) -> Option<&'a [u8]> {
+        self.notes()
+            .find(|note| note.name == name && note.n_type == n_type)
+            .map(|note| note.desc)
+    }
+
+    /// Iterates over the entries of all `PT_NOTE` segments. Malformed segments are cut short.
+    fn notes(&self) -> impl Iterator<Item = Note<'a>> + '_ {
+        self.segments()
+            .iter()
+            .filter(|segment| segment.p_type == PT_NOTE)
+            .filter_map(|segment| {
+                let align = if segment.p_align == 8 { 8 } else { 4 };
+                let bytes = self.bytes(segment.p_vaddr, segment.p_filesz)?;
+                Some(NoteIter { bytes, align })
+            })
+            .flatten()
+    }
+}
+
+struct NoteIter<'a> {
+    bytes: &'a [u8],
+    align: usize,
+}
+
+impl<'a> Iterator for NoteIter<'a> {
+    type Item = Note<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let word = |at: usize| {
+            Some(u32::from_le_bytes(
+                self.bytes.get(at..at + 4)?.try_into().ok()?,
+            ))
+        };
+        let namesz = word(0)? as usize;
+        let descsz = word(4)? as usize;
+        let n_type = word(8)?;
+        let name_end = 12usize.checked_add(namesz)?;
+        let desc_start = name_end.next_multiple_of(self.align);
+        let desc_end = desc_start.checked_add(descsz)?;
+        let name = self.bytes.get(12..name_end)?;
+        let desc = self.bytes.get(desc_start..desc_end)?;
+        let next = desc_end.next_multiple_of(self.align).min(self.bytes.len());
+        self.bytes = &self.bytes[next..];
+        Some(Note {
+            // The name is NUL-terminated.
+            name: name.strip_suffix(&[0]).unwrap_or(name),
+            n_type,
+            desc,
+        })
+    }
+}
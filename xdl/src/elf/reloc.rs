@@ -0,0 +1,464 @@
+use super::{Class, Dynamic, EM_386, EM_AARCH64, EM_ARM, EM_X86_64, Image};
+use crate::Error;
+
+/// The table a relocation was read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RelocationTable {
+    /// `DT_REL`
+    Rel,
+    /// `DT_RELA`
+    Rela,
+    /// `DT_JMPREL` (PLT relocations, `REL` or `RELA` depending on `DT_PLTREL`)
+    JmpRel,
+    /// `DT_RELR` (or the pre-standard `DT_ANDROID_RELR`), relative relocations only
+    Relr,
+    /// `DT_ANDROID_REL`, Android's packed (`APS2`) format
+    AndroidRel,
+    /// `DT_ANDROID_RELA`, Android's packed (`APS2`) format
+    AndroidRela,
+}
+
+/// Architecture independent classification of a relocation type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RelocationKind {
+    /// `R_*_NONE`
+    None,
+    /// Word-sized absolute address of the symbol (`R_AARCH64_ABS64`, `R_ARM_ABS32`, ...).
+    Absolute,
+    /// GOT entry of a data or function symbol.
+    GlobDat,
+    /// PLT slot of a function symbol.
+    JumpSlot,
+    /// Load bias plus addend.
+    Relative,
+    /// Result of an IFUNC resolver.
+    IRelative,
+    /// Any of the thread-local storage relocations.
+    Tls,
+    /// Any other relocation type.
+    Other,
+}
+
+impl RelocationKind {
+    /// Classifies the relocation type `r_type` of the architecture `machine` (`e_machine`).
+    pub fn classify(machine: u16, r_type: u32) -> Self {
+        use RelocationKind::*;
+        match (machine, r_type) {
+            (_, 0) => None,
+            (EM_AARCH64, 257) | (EM_ARM, 2) | (EM_386, 1) | (EM_X86_64, 1) => Absolute,
+            (EM_AARCH64, 1025) | (EM_ARM, 21) | (EM_386, 6) | (EM_X86_64, 6) => GlobDat,
+            (EM_AARCH64, 1026) | (EM_ARM, 22) | (EM_386, 7) | (EM_X86_64, 7) => JumpSlot,
+            (EM_AARCH64, 1027) | (EM_ARM, 23) | (EM_386, 8) | (EM_X86_64, 8) => Relative,
+            (EM_AARCH64, 1032) | (EM_ARM, 160) | (EM_386, 42) | (EM_X86_64, 37) => IRelative,
+            (EM_AARCH64, 1028..=1031)
+            | (EM_ARM, 13 | 17..=19)
+            | (EM_386, 14 | 35..=37 | 41)
+            | (EM_X86_64, 16..=18 | 36) => Tls,
+            _ => Other,
+        }
+    }
+
+    /// The relative relocation type of the architecture `machine`, used for `RELR` entries.
+    fn relative_type(machine: u16) -> u32 {
+        match machine {
+            EM_AARCH64 => 1027,
+            EM_ARM => 23,
+            _ => 8,
+        }
+    }
+}
+
+/// The symbol referenced by a [`Relocation`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelocationSymbol {
+    /// Index of the symbol in `.dynsym`.
+    pub index: u32,
+    /// Name of the symbol, empty if it could not be read.
+    pub name: String,
+    /// `true` if the symbol is not defined by the module itself (an import).
+    pub undefined: bool,
+}
+
+/// A decoded relocation entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Relocation {
+    /// Virtual address of the relocated location (add the load bias to get its address).
+    pub offset: u64,
+    /// Architecture specific relocation type (`R_*`).
+    pub r_type: u32,
+    /// Classification of [`r_type`](Self::r_type).
+    pub kind: RelocationKind,
+    /// Referenced symbol, `None` for relocations without one (e.g. relative relocations).
+    pub symbol: Option<RelocationSymbol>,
+    /// Explicit addend, `None` if the addend is stored at the relocated location (`REL`, `RELR`).
+    pub addend: Option<i64>,
+    /// The table this entry was read from.
+    pub table: RelocationTable,
+}
+
+/// Iterator over the relocations of a module, see [`Library::relocations`](crate::Library::relocations).
+#[derive(Debug, Clone)]
+pub struct Relocations {
+    inner: std::vec::IntoIter<Relocation>,
+}
+
+impl Iterator for Relocations {
+    type Item = Relocation;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl ExactSizeIterator for Relocations {}
+
+/// A relocation entry before its symbol is looked up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RawRelocation {
+    pub offset: u64,
+    pub r_type: u32,
+    pub sym: u32,
+    pub addend: Option<i64>,
+    pub table: RelocationTable,
+}
+
+const RELOCATION_GROUPED_BY_INFO_FLAG: u64 = 1;
+const RELOCATION_GROUPED_BY_OFFSET_DELTA_FLAG: u64 = 2;
+const RELOCATION_GROUPED_BY_ADDEND_FLAG: u64 = 4;
+const RELOCATION_GROUP_HAS_ADDEND_FLAG: u64 = 8;
+
+impl<'a> Image<'a> {
+    /// Decodes all relocation tables and looks up the referenced symbols.
+    pub(crate) fn relocations(&self) -> Result<Relocations, Error> {
+        let dynamic = &self.require_dynamic()?;
+        let relocations = self
+            .raw_relocations(dynamic)?
+            .into_iter()
+            .map(|reloc| Relocation {
+                offset: reloc.offset,
+                r_type: reloc.r_type,
+                kind: self.relocation_kind(reloc.r_type),
+                symbol: (reloc.sym != 0).then(|| {
+                    let sym = self.dynsym(dynamic, reloc.sym as u64);
+                    RelocationSymbol {
+                        index: reloc.sym,
+                        name: sym
+                            .and_then(|sym| self.dynstr(dynamic, sym.st_name as u64))
+                            .map(|name| name.to_string_lossy().into_owned())
+                            .unwrap_or_default(),
+                        undefined: sym.is_some_and(|sym| sym.is_undefined()),
+                    }
+                }),
+                addend: reloc.addend,
+                table: reloc.table,
+            })
+            .collect::<Vec<_>>();
+        Ok(Relocations {
+            inner: relocations.into_iter(),
+        })
+    }
+
+    /// Decodes `DT_REL`, `DT_RELA`, `DT_JMPREL`, `DT_RELR` and the packed Android tables.
+    ///
+    /// Fails with [`Error::InvalidElf`] if a table describes more relocations than the module
+    /// has words, or addresses that overflow. Truncated tables are decoded up to the end of the
+    /// segment they are in.
+    pub(crate) fn raw_relocations(&self, dynamic: &Dynamic) -> Result<Vec<RawRelocation>, Error> {
+        let mut relocations = Vec::new();
+        if let Some((vaddr, size)) = dynamic.rel {
+            self.decode_table(vaddr, size, false, RelocationTable::Rel, &mut relocations);
+        }
+        if let Some((vaddr, size)) = dynamic.rela {
+            self.decode_table(vaddr, size, true, RelocationTable::Rela, &mut relocations);
+        }
+        if let Some((vaddr, size)) = dynamic.android_rel {
+            self.decode_packed(vaddr, size, false, &mut relocations)?;
+        }
+        if let Some((vaddr, size)) = dynamic.android_rela {
+            self.decode_packed(vaddr, size, true, &mut relocations)?;
+        }
+        if let Some((vaddr, size)) = dynamic.relr {
+            self.decode_relr(vaddr, size, &mut relocations)?;
+        }
+        if let Some(vaddr) = dynamic.jmprel {
+            let rela = dynamic.pltrel == super::DT_RELA;
+            let size = dynamic.pltrelsz;
+            self.decode_table(vaddr, size, rela, RelocationTable::JmpRel, &mut relocations);
+        }
+        Ok(relocations)
+    }
+
+    fn decode_table(
+        &self,
+        vaddr: u64,
+        size: u64,
+        rela: bool,
+        table: RelocationTable,
+        out: &mut Vec<RawRelocation>,
+    ) {
+        let word = self.class().word_size();
+        let entsize = if rela { word * 3 } else { word * 2 };
+        for i in 0..size / entsize {
+            // Entries past the end of the address space can not be read either.
+            let Some(entry) = i.checked_mul(entsize).and_then(|at| vaddr.checked_add(at)) else {
+                break;
+            };
+            let (Some(offset), Some(info)) = (self.read_word(entry), self.read_word(entry + word))
+            else {
+                break;
+            };
+            let addend = if rela {
+                match self.read_word(entry + word * 2) {
+                    Some(addend) => Some(self.sign_extend(addend)),
+                    None => break,
+                }
+            } else {
+                None
+            };
+            let (sym, r_type) = self.split_info(info);
+            out.push(RawRelocation {
+                offset,
+                r_type,
+                sym,
+                addend,
+                table,
+            });
+        }
+    }
+
+    /// Decodes Android's packed relocations, as produced by `lld --pack-dyn-relocs=android`.
+    ///
+    /// The table starts with the magic `APS2`, followed by a stream of SLEB128 values: the
+    /// relocation count and initial offset, then groups of relocations sharing some fields.
+    fn decode_packed(
+        &self,
+        vaddr: u64,
+        size: u64,
+        rela: bool,
+        out: &mut Vec<RawRelocation>,
+    ) -> Result<(), Error> {
+        let Some(bytes) = self.bytes(vaddr, size) else {
+            return Ok(());
+        };
+        let Some(stream) = bytes.strip_prefix(b"APS2") else {
+            return Ok(());
+        };
+        let table = if rela {
+            RelocationTable::AndroidRela
+        } else {
+            RelocationTable::AndroidRel
+        };
+        let mut decoder = Sleb128::new(stream, self.class());
+        let (Some(count), Some(mut offset)) = (decoder.next(), decoder.next()) else {
+            return Ok(());
+        };
+        // Grouped fields take no space in the stream: bound the count by the module size
+        // instead, so that a corrupted count can not make the decoder loop (almost) forever.
+        if count > self.word_count() {
+            return Err(Error::InvalidElf(format!(
+                "packed relocation count {} exceeds the size of the module",
+                count
+            )));
+        }
+        let (mut info, mut addend) = (0u64, 0u64);
+        let mut decoded = 0u64;
+        while decoded < count {
+            let (Some(group_size), Some(flags)) = (decoder.next(), decoder.next()) else {
+                return Ok(());
+            };
+            let group_size = group_size.min(count - decoded);
+            let grouped_by_info = flags & RELOCATION_GROUPED_BY_INFO_FLAG != 0;
+            let grouped_by_offset_delta = flags & RELOCATION_GROUPED_BY_OFFSET_DELTA_FLAG != 0;
+            let grouped_by_addend = flags & RELOCATION_GROUPED_BY_ADDEND_FLAG != 0;
+            let has_addend = flags & RELOCATION_GROUP_HAS_ADDEND_FLAG != 0;
+            let group_offset_delta = if grouped_by_offset_delta {
+                let Some(delta) = decoder.next() else {
+                    return Ok(());
+                };
+                delta
+            } else {
+                0
+            };
+            if grouped_by_info {
+                let Some(value) = decoder.next() else {
+                    return Ok(());
+                };
+                info = value;
+            }
+            if has_addend && grouped_by_addend {
+                if !rela {
+                    return Err(Error::InvalidElf(
+                        "packed REL relocations with an addend".to_string(),
+                    ));
+                }
+                let Some(delta) = decoder.next() else {
+                    return Ok(());
+                };
+                addend = addend.wrapping_add(delta);
+            } else if !has_addend {
+                addend = 0;
+            }
+            for _ in 0..group_size {
+                let delta = if grouped_by_offset_delta {
+                    group_offset_delta
+                } else {
+                    let Some(delta) = decoder.next() else {
+                        return Ok(());
+                    };
+                    delta
+                };
+                // Offsets wrap around like in the linker, deltas may be negative.
+                offset = offset.wrapping_add(delta);
+                if !grouped_by_info {
+                    let Some(value) = decoder.next() else {
+                        return Ok(());
+                    };
+                    info = value;
+                }
+                if has_addend && !grouped_by_addend {
+                    let Some(delta) = decoder.next() else {
+                        return Ok(());
+                    };
+                    addend = addend.wrapping_add(delta);
+                }
+                let (sym, r_type) = self.split_info(info);
+                out.push(RawRelocation {
+                    offset: self.truncate(offset),
+                    r_type,
+                    sym,
+                    addend: rela.then(|| self.sign_extend(addend)),
+                    table,
+                });
+            }
+            decoded += group_size;
+        }
+        Ok(())
+    }
+
+    /// Decodes a `RELR` table: an even entry is the address of a relative relocation, an odd
+    /// entry is a bitmap of the following `word_bits - 1` words to relocate.
+    fn decode_relr(
+        &self,
+        vaddr: u64,
+        size: u64,
+        out: &mut Vec<RawRelocation>,
+    ) -> Result<(), Error> {
+        let word = self.class().word_size();
+        let r_type = RelocationKind::relative_type(self.machine());
+        let overflow = || Error::InvalidElf("RELR entry out of the address space".to_string());
+        let push = |out: &mut Vec<RawRelocation>, offset: u64| {
+            out.push(RawRelocation {
+                offset,
+                r_type,
+                sym: 0,
+                addend: None,
+                table: RelocationTable::Relr,
+            })
+        };
+        let mut base = 0u64;
+        for i in 0..size / word {
+            let Some(entry) = i.checked_mul(word).and_then(|at| vaddr.checked_add(at)) else {
+                break;
+            };
+            let Some(entry) = self.read_word(entry) else {
+                break;
+            };
+            if entry & 1 == 0 {
+                push(out, entry);
+                base = entry.checked_add(word).ok_or_else(overflow)?;
+            } else {
+                let mut bitmap = entry >> 1;
+                let mut offset = base;
+                while bitmap != 0 {
+                    if bitmap & 1 != 0 {
+                        push(out, offset);
+                    }
+                    bitmap >>= 1;
+                    offset = offset.checked_add(word).ok_or_else(overflow)?;
+                }
+                base = base
+                    .checked_add((word * 8 - 1) * word)
+                    .ok_or_else(overflow)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Number of words covered by the `PT_LOAD` segments, an upper bound of the number of
+    /// relocations.
+    fn word_count(&self) -> u64 {
+        self.segments()
+            .iter()
+            .filter(|segment| segment.p_type == super::PT_LOAD)
+            .fold(0u64, |total, segment| total.saturating_add(segment.p_memsz))
+            / self.class().word_size()
+    }
+
+    /// Splits `r_info` into the symbol index and the relocation type.
+    pub(crate) fn split_info(&self, info: u64) -> (u32, u32) {
+        match self.class() {
+            Class::Elf32 => ((info as u32) >> 8, info as u32 & 0xff),
+            Class::Elf64 => ((info >> 32) as u32, info as u32),
+        }
+    }
+
+    pub(crate) fn sign_extend(&self, value: u64) -> i64 {
+        match self.class() {
+            Class::Elf32 => value as u32 as i32 as i64,
+            Class::Elf64 => value as i64,
+        }
+    }
+
+    fn truncate(&self, value: u64) -> u64 {
+        match self.class() {
+            Class::Elf32 => value as u32 as u64,
+            Class::Elf64 => value,
+        }
+    }
+
+    /// Classifies a relocation type of this image's architecture.
+    pub(crate) fn relocation_kind(&self, r_type: u32) -> RelocationKind {
+        RelocationKind::classify(self.machine(), r_type)
+    }
+}
+
+/// Decoder for the SLEB128 stream of packed relocations.
+///
+/// Values are word-sized: they wrap around on 32-bit images, just like in the linker.
+struct Sleb128<'a> {
+    bytes: &'a [u8],
+    class: Class,
+}
+
+impl<'a> Sleb128<'a> {
+    fn new(bytes: &'a [u8], class: Class) -> Self {
+        Self { bytes, class }
+    }
+
+    fn next(&mut self) -> Option<u64> {
+        let bits = self.class.word_size() as u32 * 8;
+        let mut value = 0u64;
+        let mut shift = 0u32;
+        loop {
+            let (&byte, rest) = self.bytes.split_first()?;
+            self.bytes = rest;
+            if shift < 64 {
+                value |= ((byte & 0x7f) as u64) << shift;
+            }
+            shift += 7;
+            if byte & 0x80 == 0 {
+                if shift < bits && byte & 0x40 != 0 {
+                    value |= u64::MAX << shift;
+                }
+                return Some(match self.class {
+                    Class::Elf32 => value as u32 as u64,
+                    Class::Elf64 => value,
+                });
+            }
+        }
+    }
+}
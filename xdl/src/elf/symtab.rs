@@ -0,0 +1,44 @@
+use super::{ElfFile, ElfSymbol};
+use std::collections::HashMap;
+use std::fmt;
+
+/// The debugging symbols of a module, indexed by name: `.symtab`, followed by the `.symtab` of
+/// the MiniDebugInfo stored compressed in `.gnu_debugdata`.
+///
+/// Like `xdl_dsym`, only defined symbols are kept and the first definition of a name wins.
+#[derive(Default)]
+pub(crate) struct DebugSymbols {
+    symbols: HashMap<Vec<u8>, ElfSymbol>,
+}
+
+impl DebugSymbols {
+    /// Indexes `file`, and `debugdata`, the ELF file decompressed from its `.gnu_debugdata`.
+    pub(crate) fn new(file: &ElfFile, debugdata: Option<&ElfFile>) -> Self {
+        let mut symbols = HashMap::new();
+        let tables = std::iter::once(file).chain(debugdata);
+        for symbol in tables.flat_map(ElfFile::symtab) {
+            if symbol.defined && symbol.value != 0 {
+                symbols
+                    .entry(symbol.name.clone().into_bytes())
+                    .or_insert(symbol);
+            }
+        }
+        Self { symbols }
+    }
+
+    pub(crate) fn get(&self, name: &[u8]) -> Option<&ElfSymbol> {
+        self.symbols.get(name)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.symbols.len()
+    }
+}
+
+impl fmt::Debug for DebugSymbols {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DebugSymbols")
+            .field("len", &self.len())
+            .finish()
+    }
+}
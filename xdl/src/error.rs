@@ -1,5 +1,6 @@
 use self::Error::*;
 use std::fmt::{Display, Formatter};
+use std::time::Duration;
 
 /// This is a library-specific error that is returned by all calls to all APIs.
 #[derive(Debug)]
@@ -8,6 +9,20 @@ pub enum Error {
     OpeningLibraryError(String),
     /// The symbol could not be found.
     SymbolNotFound(String),
+    /// The symbol was found, but its size, alignment or type does not match the request.
+    InvalidSymbol(String),
+    /// The ELF structures of the library could not be parsed.
+    InvalidElf(String),
+    /// The loader hook could not be installed.
+    LoaderHookError(String),
+    /// The library did not get loaded within the given time.
+    WaitTimeout(String, Duration),
+    /// The offset database could not be read or is inconsistent.
+    OffsetDatabaseError(String),
+    /// The running build of a library is not in the offset database.
+    UnknownBuild(String),
+    /// More than one loaded instance matches the library name; the candidate paths are listed.
+    AmbiguousLibrary(String, Vec<String>),
 }
 
 impl Display for Error {
@@ -15,6 +30,24 @@ impl Display for Error {
         match self {
             OpeningLibraryError(msg) => write!(f, "Could not open library: {}", msg),
             SymbolNotFound(symbol) => write!(f, "Symbol `{}` not found", symbol),
+            InvalidSymbol(msg) => write!(f, "Invalid symbol: {}", msg),
+            InvalidElf(msg) => write!(f, "Invalid ELF: {}", msg),
+            LoaderHookError(msg) => write!(f, "Could not hook the loader: {}", msg),
+            WaitTimeout(name, timeout) => {
+                write!(
+                    f,
+                    "Timed out after {:?} waiting for library `{}`",
+                    timeout, name
+                )
+            }
+            OffsetDatabaseError(msg) => write!(f, "Invalid offset database: {}", msg),
+            UnknownBuild(msg) => write!(f, "Build not found in the offset database: {}", msg),
+            AmbiguousLibrary(name, candidates) => write!(
+                f,
+                "Library `{}` is loaded more than once: {}",
+                name,
+                candidates.join(", ")
+            ),
         }
     }
 }
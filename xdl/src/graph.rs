@@ -0,0 +1,457 @@
+/*!
+Dependency graph of the modules loaded into the process.
+
+[`DependencyGraph::build`] reads the `DT_NEEDED` entries of every loaded module and resolves each
+of them to the loaded instance the linker most likely used. The result can be checked for
+[missing](DependencyGraph::missing) and [duplicated](DependencyGraph::duplicates) libraries, or
+exported to Graphviz DOT and JSON.
+
+```no_run
+# #[cfg(target_os = "android")]
+# fn example() {
+use android_xdl::graph::DependencyGraph;
+
+let graph = DependencyGraph::build();
+for missing in graph.missing() {
+    println!("{} needs {}", missing.module.path().to_string_lossy(), missing.needed);
+}
+std::fs::write("/sdcard/deps.dot", graph.to_dot()).unwrap();
+# }
+```
+*/
+use crate::elf::Dependencies;
+use crate::module::ModuleInfo;
+#[cfg(target_os = "android")]
+use crate::{elf::Image, module::iterate, raw::api::XDL_FULL_PATHNAME};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::ops::ControlFlow;
+
+/// A loaded module and its resolved dependencies.
+#[derive(Debug, Clone)]
+pub struct Node {
+    /// The module.
+    pub module: ModuleInfo,
+    /// The dependencies it declares. Empty if its dynamic section could not be read.
+    pub dependencies: Dependencies,
+    /// One edge per `DT_NEEDED` entry, in declaration order.
+    pub edges: Vec<Edge>,
+}
+
+impl Node {
+    /// `DT_SONAME` of the module, or its basename if it has none.
+    pub fn name(&self) -> String {
+        self.dependencies
+            .soname
+            .clone()
+            .unwrap_or_else(|| self.module.basename().to_string_lossy().into_owned())
+    }
+}
+
+/// A `DT_NEEDED` entry of a [`Node`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edge {
+    /// The needed name, as written in the dynamic section.
+    pub needed: String,
+    /// Index in [`DependencyGraph::nodes`] of the loaded instance, `None` if none is loaded.
+    pub target: Option<usize>,
+}
+
+/// A dependency that is not loaded, see [`DependencyGraph::missing`].
+#[derive(Debug, Clone, Copy)]
+pub struct Missing<'a> {
+    /// The module declaring the dependency.
+    pub module: &'a ModuleInfo,
+    /// The needed name.
+    pub needed: &'a str,
+}
+
+/// Dependency graph of the loaded modules, see the [module documentation](self).
+#[derive(Debug, Clone, Default)]
+pub struct DependencyGraph {
+    nodes: Vec<Node>,
+}
+
+impl DependencyGraph {
+    /// Builds the graph from a snapshot of the currently loaded modules.
+    ///
+    /// The dynamic section of each module is read inside `xdl_iterate_phdr`, where the linker
+    /// lock keeps it from being unloaded. Modules without a readable dynamic section are
+    /// reported without dependencies.
+    #[cfg(target_os = "android")]
+    pub fn build() -> Self {
+        let mut nodes = Vec::new();
+        iterate(XDL_FULL_PATHNAME, |info, size| {
+            let module = unsafe { ModuleInfo::from_raw(info, size) };
+            let image = unsafe { Image::from_memory(module.base(), module.phdrs()) };
+            let dependencies = image.dependencies().unwrap_or_default();
+            nodes.push(Node {
+                module,
+                dependencies,
+                edges: Vec::new(),
+            });
+            ControlFlow::Continue(())
+        });
+        Self::from_nodes(nodes)
+    }
+
+    /// Builds the graph from modules in load order, resolving the edges of each node.
+    fn from_nodes(nodes: Vec<Node>) -> Self {
+        let mut graph = Self { nodes };
+        for index in 0..graph.nodes.len() {
+            let edges = graph.nodes[index]
+                .dependencies
+                .needed
+                .iter()
+                .map(|needed| Edge {
+                    needed: needed.clone(),
+                    target: graph.resolve_from(index, needed),
+                })
+                .collect();
+            graph.nodes[index].edges = edges;
+        }
+        graph
+    }
+
+    /// All loaded modules, in load order.
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    /// Returns the index of the first module matching `name` (a soname, basename or full path).
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.candidates(name).next()
+    }
+
+    /// Returns the dependencies of the module at `index`, followed transitively, in
+    /// breadth-first order. Missing dependencies are skipped.
+    pub fn transitive_dependencies(&self, index: usize) -> Vec<usize> {
+        let mut visited = vec![index];
+        let mut next = 0;
+        while let Some(&current) = visited.get(next) {
+            next += 1;
+            for target in self.nodes[current]
+                .edges
+                .iter()
+                .filter_map(|edge| edge.target)
+            {
+                if !visited.contains(&target) {
+                    visited.push(target);
+                }
+            }
+        }
+        visited.remove(0);
+        visited
+    }
+
+    /// Lists the `DT_NEEDED` entries without a loaded instance.
+    pub fn missing(&self) -> Vec<Missing<'_>> {
+        self.nodes
+            .iter()
+            .flat_map(|node| {
+                node.edges
+                    .iter()
+                    .filter(|edge| edge.target.is_none())
+                    .map(|edge| Missing {
+                        module: &node.module,
+                        needed: &edge.needed,
+                    })
+            })
+            .collect()
+    }
+
+    /// Groups the modules loaded more than once under the same name (e.g. from two different
+    /// paths, or in two linker namespaces). Each group holds node indices, in load order.
+    pub fn duplicates(&self) -> Vec<Vec<usize>> {
+        let mut groups: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for (index, node) in self.nodes.iter().enumerate() {
+            if !node.module.path().is_empty() {
+                groups.entry(node.name()).or_default().push(index);
+            }
+        }
+        groups
+            .into_values()
+            .filter(|group| group.len() > 1)
+            .collect()
+    }
+
+    /// Exports the graph in Graphviz DOT format.
+    ///
+    /// Missing dependencies are drawn as dashed red nodes and duplicated modules in orange.
+    pub fn to_dot(&self) -> String {
+        let duplicated = self.duplicates().concat();
+        let mut dot = String::from("digraph dependencies {\n    node [shape=box];\n");
+        for (index, node) in self.nodes.iter().enumerate() {
+            let color = if duplicated.contains(&index) {
+                ", color=orange"
+            } else {
+                ""
+            };
+            let label = format!("{}\n{}", node.name(), node.module.path().to_string_lossy());
+            let _ = writeln!(
+                dot,
+                "    n{} [label={}{}];",
+                index,
+                dot_string(&label),
+                color
+            );
+        }
+        for (index, node) in self.nodes.iter().enumerate() {
+            for edge in &node.edges {
+                let _ = match edge.target {
+                    Some(target) => writeln!(dot, "    n{} -> n{};", index, target),
+                    None => writeln!(
+                        dot,
+                        "    n{} -> {} [style=dashed, color=red];",
+                        index,
+                        dot_string(&format!("missing: {}", edge.needed))
+                    ),
+                };
+            }
+        }
+        for missing in self.missing() {
+            let _ = writeln!(
+                dot,
+                "    {} [style=dashed, color=red];",
+                dot_string(&format!("missing: {}", missing.needed))
+            );
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Exports the graph as JSON.
+    ///
+    /// The document has a `modules` array (path, base, soname, runpath and the needed entries
+    /// with the path they resolved to), plus the `missing` and `duplicates` reports.
+    pub fn to_json(&self) -> String {
+        let path = |index: usize| json_string(&self.nodes[index].module.path().to_string_lossy());
+        let mut json = String::from("{\"modules\":[");
+        for (index, node) in self.nodes.iter().enumerate() {
+            if index > 0 {
+                json.push(',');
+            }
+            let needed = node
+                .edges
+                .iter()
+                .map(|edge| {
+                    format!(
+                        "{{\"name\":{},\"resolved\":{}}}",
+                        json_string(&edge.needed),
+                        edge.target.map_or_else(|| "null".to_string(), path)
+                    )
+                })
+                .collect::<Vec<_>>();
+            let runpath = node
+                .dependencies
+                .runpath
+                .iter()
+                .map(|dir| json_string(dir))
+                .collect::<Vec<_>>();
+            let _ = write!(
+                json,
+                "{{\"path\":{},\"base\":\"{:#x}\",\"soname\":{},\"runpath\":[{}],\"needed\":[{}]}}",
+                path(index),
+                node.module.base(),
+                node.dependencies
+                    .soname
+                    .as_deref()
+                    .map_or_else(|| "null".to_string(), json_string),
+                runpath.join(","),
+                needed.join(",")
+            );
+        }
+        json.push_str("],\"missing\":[");
+        let missing = self
+            .missing()
+            .iter()
+            .map(|missing| {
+                format!(
+                    "{{\"module\":{},\"needed\":{}}}",
+                    json_string(&missing.module.path().to_string_lossy()),
+                    json_string(missing.needed)
+                )
+            })
+            .collect::<Vec<_>>();
+        json.push_str(&missing.join(","));
+        json.push_str("],\"duplicates\":[");
+        let duplicates = self
+            .duplicates()
+            .into_iter()
+            .map(|group| {
+                let paths = group.into_iter().map(path).collect::<Vec<_>>();
+                format!("[{}]", paths.join(","))
+            })
+            .collect::<Vec<_>>();
+        json.push_str(&duplicates.join(","));
+        json.push_str("]}");
+        json
+    }
+
+    /// Modules that could satisfy `needed`: matched by full path if it contains a `/`,
+    /// otherwise by soname or basename.
+    fn candidates<'a>(&'a self, needed: &'a str) -> impl Iterator<Item = usize> + 'a {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter_map(move |(index, node)| {
+                let matches = node.module.matches(needed)
+                    || (!needed.contains('/')
+                        && node.dependencies.soname.as_deref() == Some(needed));
+                matches.then_some(index)
+            })
+    }
+
+    /// Picks the instance of `needed` loaded for the module at `from`.
+    ///
+    /// The linker does not expose this, so when several instances are loaded the one in the
+    /// same directory is preferred, then one from `DT_RUNPATH`, then the first one loaded.
+    fn resolve_from(&self, from: usize, needed: &str) -> Option<usize> {
+        let candidates = self.candidates(needed).collect::<Vec<_>>();
+        if candidates.len() <= 1 {
+            return candidates.first().copied();
+        }
+        let node = &self.nodes[from];
+        let path = node.module.path().to_string_lossy();
+        let origin = path.rsplit_once('/').map_or("", |(dir, _)| dir);
+        let in_dir = |dir: &str| {
+            let dir = dir.replace("$ORIGIN", origin).replace("${ORIGIN}", origin);
+            let dir = dir.trim_end_matches('/').to_string();
+            candidates.iter().copied().find(|&index| {
+                let path = self.nodes[index].module.path().to_string_lossy();
+                path.rsplit_once('/')
+                    .is_some_and(|(parent, _)| parent == dir)
+            })
+        };
+        std::iter::once(origin)
+            .chain(node.dependencies.runpath.iter().map(String::as_str))
+            .find_map(in_dir)
+            .or(candidates.first().copied())
+    }
+}
+
+/// Quotes a string for DOT.
+fn dot_string(s: &str) -> String {
+    format!(
+        "\"{}\"",
+        s.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    )
+}
+
+/// Quotes a string for JSON.
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(path: &str, soname: Option<&str>, needed: &[&str]) -> Node {
+        Node {
+            module: ModuleInfo::synthetic(path, 0),
+            dependencies: Dependencies {
+                soname: soname.map(str::to_string),
+                needed: needed.iter().map(|name| name.to_string()).collect(),
+                ..Default::default()
+            },
+            edges: Vec::new(),
+        }
+    }
+
+    fn targets(graph: &DependencyGraph, index: usize) -> Vec<Option<usize>> {
+        graph.nodes()[index]
+            .edges
+            .iter()
+            .map(|edge| edge.target)
+            .collect()
+    }
+
+    #[test]
+    fn diamond() {
+        // app -> (left, right) -> base
+        let graph = DependencyGraph::from_nodes(vec![
+            node(
+                "/data/app/lib/libapp.so",
+                None,
+                &["libleft.so", "libright.so"],
+            ),
+            node("/data/app/lib/libleft.so", None, &["libbase.so"]),
+            node("/data/app/lib/libright.so", None, &["libbase.so"]),
+            node("/data/app/lib/libbase.so", None, &[]),
+        ]);
+        assert_eq!(targets(&graph, 0), [Some(1), Some(2)]);
+        assert_eq!(targets(&graph, 1), [Some(3)]);
+        assert_eq!(targets(&graph, 2), [Some(3)]);
+        assert_eq!(graph.transitive_dependencies(0), [1, 2, 3]);
+        assert_eq!(graph.transitive_dependencies(1), [3]);
+        assert!(graph.transitive_dependencies(3).is_empty());
+        assert!(graph.missing().is_empty());
+        assert!(graph.duplicates().is_empty());
+    }
+
+    #[test]
+    fn cycle() {
+        let graph = DependencyGraph::from_nodes(vec![
+            node("/system/lib64/liba.so", None, &["libb.so"]),
+            node("/system/lib64/libb.so", None, &["libc.so"]),
+            node("/system/lib64/libc.so", None, &["liba.so"]),
+        ]);
+        assert_eq!(graph.transitive_dependencies(0), [1, 2]);
+        assert_eq!(graph.transitive_dependencies(1), [2, 0]);
+        assert_eq!(graph.transitive_dependencies(2), [0, 1]);
+    }
+
+    #[test]
+    fn needed_by_soname() {
+        let graph = DependencyGraph::from_nodes(vec![
+            node(
+                "/data/app/lib/libmain.so",
+                None,
+                &["libfoo.so.1", "libmissing.so"],
+            ),
+            node("/data/app/lib/libfoo-impl.so", Some("libfoo.so.1"), &[]),
+        ]);
+        assert_eq!(targets(&graph, 0), [Some(1), None]);
+        assert_eq!(graph.find("libfoo.so.1"), Some(1));
+        assert_eq!(graph.find("libfoo-impl.so"), Some(1));
+        assert_eq!(graph.nodes()[1].name(), "libfoo.so.1");
+        assert_eq!(graph.transitive_dependencies(0), [1]);
+
+        let missing = graph.missing();
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].needed, "libmissing.so");
+        assert_eq!(missing[0].module.path(), c"/data/app/lib/libmain.so");
+    }
+
+    #[test]
+    fn duplicates_resolve_to_the_same_directory() {
+        let graph = DependencyGraph::from_nodes(vec![
+            node("/system/lib64/libc++.so", None, &[]),
+            node("/data/app/lib/libc++.so", None, &[]),
+            node("/data/app/lib/libgame.so", None, &["libc++.so"]),
+            node("/system/lib64/libui.so", None, &["libc++.so"]),
+        ]);
+        assert_eq!(targets(&graph, 2), [Some(1)]);
+        assert_eq!(targets(&graph, 3), [Some(0)]);
+        assert_eq!(graph.duplicates(), [vec![0, 1]]);
+    }
+}
@@ -5,6 +5,8 @@ This is the FFI binding for [`xDL`](https://github.com/hexhacking/xDL)
 # Quick example
 
 ```no_run
+# #[cfg(target_os = "android")]
+# mod example {
 use android_xdl::Library;
 use android_xdl::wrapper::Container;
 use android_xdl::derive::NativeBridge;
@@ -35,6 +37,8 @@ fn main() {
         *example_reference = 5;
     }
 }
+# }
+# fn main() {}
 ```
 
 # Features
@@ -86,16 +90,42 @@ This code is licensed under the [MIT](https://github.com/suqiernb/android-xdl-rs
 #![allow(unused_imports, dead_code)]
 
 
-#[cfg(not(target_os = "android"))]
+// On other Linux targets, only the ELF decoders and the dependency graph are built, so that they
+// can be tested on a host.
+#[cfg(not(any(target_os = "android", target_os = "linux")))]
 compile_error!("android_xdl only supports compiling for Android");
 
+mod apk;
+pub mod elf;
 mod error;
+pub mod graph;
+#[cfg(target_os = "android")]
+pub mod loader;
+#[cfg(target_os = "android")]
+mod maps;
+pub mod module;
+#[cfg(feature = "offsets")]
+pub mod offsets;
+#[cfg(target_os = "android")]
 pub mod raw;
+#[cfg(target_os = "android")]
 mod symbol;
-#[cfg(feature = "wrapper")]
+#[cfg(target_os = "android")]
+pub mod watch;
+#[cfg(all(target_os = "android", feature = "wrapper"))]
 pub mod wrapper;
 #[cfg(feature = "derive")]
 pub use android_xdl_derive as derive;
 
 pub use error::Error;
-pub use symbol::{Library, Symbol};
+#[cfg(target_os = "android")]
+pub use raw::{
+    DuplicatePolicy, HandleOwnership, Namespace, OpenOptions, ResolvedSymbol, SymbolHash,
+    SymbolKind, SymbolRequest,
+};
+#[cfg(target_os = "android")]
+pub use symbol::{
+    AtomicPrimitive, CacheStats, CachedSymbol, GlobalVar, IFunc, Import, ImportTarget, LazyLibrary,
+    LazySymbol, Library, MappedImage, OwnedSymbol, Resolved, SharedLibrary, Symbol, SymbolCache,
+    TlsSymbol, WaitFor,
+};
@@ -0,0 +1,441 @@
+/*!
+Opt-in hook on the dynamic loader, to observe libraries synchronously while they get loaded.
+
+The [module watcher](crate::watch) notices a library only some time after it has been loaded, so
+a binding installed from there may come too late. Once [`install`]ed, this hook intercepts
+`dlopen` and `android_dlopen_ext` instead: pre-load callbacks run before the library is loaded, and
+post-load callbacks run right after the linker returned (its constructors have already run), but
+before the caller continues - e.g. before ART calls the library's `JNI_OnLoad`.
+
+# How it works
+
+The hook replaces GOT entries, no code is patched:
+
+* Android 8.0+: `libdl.so` forwards `dlopen` / `android_dlopen_ext` to the linker's
+  `__loader_dlopen` / `__loader_android_dlopen_ext`, passing along the caller address. Only these
+  two GOT entries of `libdl.so` are replaced, which catches every call and keeps the caller's
+  linker namespace intact.
+* Older versions: the `dlopen` / `android_dlopen_ext` imports of every loaded module are replaced,
+  and modules loaded later are patched as soon as they are loaded. The linker then sees this
+  crate's module as the caller.
+
+Calls to `dlopen` made from inside a callback are not reported again. A callback that panics is
+skipped: the panic cannot unwind into the caller of `dlopen`.
+
+# Example
+
+```no_run
+use android_xdl::loader;
+
+loader::on_post_load(|path, _flags, lib| {
+    if path.to_bytes().ends_with(b"libil2cpp.so") {
+        let _init = lib.symbol::<extern "C" fn()>(c"il2cpp_init");
+    }
+});
+unsafe { loader::install() }.unwrap();
+```
+*/
+use crate::elf::{Image, PF_R, PF_W, PF_X, PT_GNU_RELRO, RelocationKind};
+use crate::module::{self, ModuleInfo};
+use crate::{Error, Library};
+use std::cell::Cell;
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int, c_void};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock};
+
+type Result<T> = std::result::Result<T, Error>;
+
+type PreLoadCallback = dyn Fn(Option<&CStr>, c_int) + Send + Sync;
+type PostLoadCallback = dyn Fn(&CStr, c_int, &Library) + Send + Sync;
+
+/// Identifies a registered callback, see [`remove`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HookId(u64);
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+static PRE_LOAD: RwLock<Vec<(HookId, Arc<PreLoadCallback>)>> = RwLock::new(Vec::new());
+static POST_LOAD: RwLock<Vec<(HookId, Arc<PostLoadCallback>)>> = RwLock::new(Vec::new());
+
+/// Registers a callback invoked before a library is loaded, with the path and flags passed to
+/// `dlopen` (the path is `None` for `dlopen(NULL)`).
+pub fn on_pre_load<F>(callback: F) -> HookId
+where
+    F: Fn(Option<&CStr>, c_int) + Send + Sync + 'static,
+{
+    let id = HookId(NEXT_ID.fetch_add(1, Ordering::Relaxed));
+    write(&PRE_LOAD).push((id, Arc::new(callback)));
+    id
+}
+
+/// Registers a callback invoked after a library has been loaded successfully, with the path and
+/// flags passed to `dlopen` and the library behind the handle the linker returned.
+///
+/// The library is found by looking up a few of its exported symbols through the handle, first
+/// among the modules named like the path, then among all modules. Callbacks are not invoked
+/// for a library that exports no symbols, nor for `dlopen(NULL)`.
+pub fn on_post_load<F>(callback: F) -> HookId
+where
+    F: Fn(&CStr, c_int, &Library) + Send + Sync + 'static,
+{
+    let id = HookId(NEXT_ID.fetch_add(1, Ordering::Relaxed));
+    write(&POST_LOAD).push((id, Arc::new(callback)));
+    id
+}
+
+/// Unregisters a callback. Returns `false` if no callback with this id was registered.
+pub fn remove(id: HookId) -> bool {
+    let mut pre_load = write(&PRE_LOAD);
+    let mut post_load = write(&POST_LOAD);
+    let len = pre_load.len() + post_load.len();
+    pre_load.retain(|(hook, _)| *hook != id);
+    post_load.retain(|(hook, _)| *hook != id);
+    pre_load.len() + post_load.len() != len
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// `libdl.so` -> `__loader_*` entries are patched.
+    Loader,
+    /// `dlopen` / `android_dlopen_ext` imports of all modules are patched.
+    Legacy,
+}
+
+struct Slot {
+    module: (usize, usize),
+    addr: usize,
+    original: usize,
+}
+
+struct State {
+    mode: Option<Mode>,
+    slots: Vec<Slot>,
+    patched: Vec<(usize, usize)>,
+}
+
+static STATE: Mutex<State> = Mutex::new(State {
+    mode: None,
+    slots: Vec::new(),
+    patched: Vec::new(),
+});
+
+static ORIG_DLOPEN: AtomicUsize = AtomicUsize::new(0);
+static ORIG_ANDROID_DLOPEN_EXT: AtomicUsize = AtomicUsize::new(0);
+static ORIG_LOADER_DLOPEN: AtomicUsize = AtomicUsize::new(0);
+static ORIG_LOADER_ANDROID_DLOPEN_EXT: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static IN_HOOK: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Installs the loader hook. Installing it again does nothing.
+///
+/// # Safety
+/// The GOT entries of `libdl.so` (or the `dlopen` imports of every module on Android 7.1 and
+/// older) are rewritten process-wide. No other code may patch or restore these entries while
+/// the hook is installed, e.g. another PLT hooking library: it would save this crate's hooks as
+/// the originals, or restore entries this crate still forwards to. Each entry is replaced with a
+/// single pointer-sized write, so threads calling through it concurrently see either function.
+pub unsafe fn install() -> Result<()> {
+    let mut state = lock();
+    if state.mode.is_some() {
+        return Ok(());
+    }
+    let modules = module::loaded_modules();
+    if let Some(libdl) = modules.iter().find(|module| module.matches("libdl.so")) {
+        patch_module(libdl, &loader_targets(), &mut state.slots);
+        if !state.slots.is_empty() {
+            state.patched.push(key(libdl));
+            state.mode = Some(Mode::Loader);
+            return Ok(());
+        }
+    }
+    for module in &modules {
+        patch_legacy(module, &mut state);
+    }
+    if state.slots.is_empty() {
+        return Err(Error::LoaderHookError(
+            "no `dlopen` import found to intercept".to_string(),
+        ));
+    }
+    state.mode = Some(Mode::Legacy);
+    Ok(())
+}
+
+/// Removes the loader hook, restoring every patched GOT entry of the modules still loaded.
+///
+/// Registered callbacks are kept and fire again after the next [`install`].
+///
+/// # Safety
+/// As for [`install`], no other code may be patching the same GOT entries. A thread that is
+/// still inside a hooked `dlopen` finishes it normally.
+pub unsafe fn uninstall() {
+    let mut state = lock();
+    if state.mode.take().is_none() {
+        return;
+    }
+    let loaded = module::loaded_modules();
+    for slot in state.slots.drain(..) {
+        if let Some(module) = loaded.iter().find(|module| key(module) == slot.module) {
+            unsafe { write_slot(module, slot.addr, slot.original) };
+        }
+    }
+    state.patched.clear();
+}
+
+/// Returns `true` if the hook is currently installed.
+pub fn is_installed() -> bool {
+    lock().mode.is_some()
+}
+
+fn loader_targets() -> [(&'static CStr, &'static AtomicUsize, usize); 2] {
+    [
+        (
+            c"__loader_dlopen",
+            &ORIG_LOADER_DLOPEN,
+            hooked_loader_dlopen as LoaderDlopen as usize,
+        ),
+        (
+            c"__loader_android_dlopen_ext",
+            &ORIG_LOADER_ANDROID_DLOPEN_EXT,
+            hooked_loader_android_dlopen_ext as LoaderAndroidDlopenExt as usize,
+        ),
+    ]
+}
+
+fn legacy_targets() -> [(&'static CStr, &'static AtomicUsize, usize); 2] {
+    [
+        (c"dlopen", &ORIG_DLOPEN, hooked_dlopen as Dlopen as usize),
+        (
+            c"android_dlopen_ext",
+            &ORIG_ANDROID_DLOPEN_EXT,
+            hooked_android_dlopen_ext as AndroidDlopenExt as usize,
+        ),
+    ]
+}
+
+fn patch_legacy(module: &ModuleInfo, state: &mut State) {
+    if state.patched.contains(&key(module)) {
+        return;
+    }
+    state.patched.push(key(module));
+    let skip = module.contains(hooked_dlopen as Dlopen as usize)
+        || ["libdl.so", "linker", "linker64"]
+            .iter()
+            .any(|name| module.matches(name));
+    if !skip {
+        patch_module(module, &legacy_targets(), &mut state.slots);
+    }
+}
+
+/// Patches newly loaded modules when running in [`Mode::Legacy`].
+fn patch_new_modules() {
+    let mut state = lock();
+    if state.mode == Some(Mode::Legacy) {
+        for module in &module::loaded_modules() {
+            patch_legacy(module, &mut state);
+        }
+    }
+}
+
+fn patch_module(
+    module: &ModuleInfo,
+    targets: &[(&CStr, &AtomicUsize, usize)],
+    slots: &mut Vec<Slot>,
+) {
+    let image = unsafe { Image::from_memory(module.base(), module.phdrs()) };
+    let Some(dynamic) = image.dynamic() else {
+        return;
+    };
+    for reloc in image.raw_relocations(&dynamic).unwrap_or_default() {
+        let kind = image.relocation_kind(reloc.r_type);
+        let patchable = match kind {
+            RelocationKind::JumpSlot | RelocationKind::GlobDat => true,
+            RelocationKind::Absolute => reloc.addend.unwrap_or(0) == 0,
+            _ => false,
+        };
+        if reloc.sym == 0 || !patchable {
+            continue;
+        }
+        let name = image
+            .dynsym(&dynamic, reloc.sym as u64)
+            .and_then(|sym| image.dynstr(&dynamic, sym.st_name as u64));
+        let Some((_, original, hook)) = targets.iter().find(|(target, ..)| Some(*target) == name)
+        else {
+            continue;
+        };
+        let addr = module.base().wrapping_add(reloc.offset as usize);
+        let current = unsafe { std::ptr::read_volatile(addr as *const usize) };
+        if current == *hook {
+            continue;
+        }
+        let _ = original.compare_exchange(0, current, Ordering::AcqRel, Ordering::Acquire);
+        if unsafe { write_slot(module, addr, *hook) } {
+            slots.push(Slot {
+                module: key(module),
+                addr,
+                original: current,
+            });
+        }
+    }
+}
+
+/// Overwrites a pointer-sized slot of a loaded module, restoring the page protection afterwards.
+unsafe fn write_slot(module: &ModuleInfo, addr: usize, value: usize) -> bool {
+    unsafe {
+        let page_size = libc::sysconf(libc::_SC_PAGESIZE) as usize;
+        let page = (addr & !(page_size - 1)) as *mut c_void;
+        let len = if (addr & (page_size - 1)) + size_of::<usize>() > page_size {
+            page_size * 2
+        } else {
+            page_size
+        };
+        if libc::mprotect(page, len, libc::PROT_READ | libc::PROT_WRITE) != 0 {
+            return false;
+        }
+        std::ptr::write_volatile(addr as *mut usize, value);
+        libc::mprotect(page, len, protection(module, addr));
+        true
+    }
+}
+
+/// Protection of the page containing `addr` after relocation.
+fn protection(module: &ModuleInfo, addr: usize) -> c_int {
+    let vaddr = addr.wrapping_sub(module.base()) as u64;
+    let image = unsafe { Image::from_memory(module.base(), module.phdrs()) };
+    let relro = image.segments().iter().any(|segment| {
+        segment.p_type == PT_GNU_RELRO
+            && vaddr >= segment.p_vaddr
+            && vaddr - segment.p_vaddr < segment.p_memsz
+    });
+    if relro {
+        return libc::PROT_READ;
+    }
+    let flags = image.load_segment(vaddr).map_or(PF_R | PF_W, |s| s.p_flags);
+    let mut prot = 0;
+    if flags & PF_R != 0 {
+        prot |= libc::PROT_READ;
+    }
+    if flags & PF_W != 0 {
+        prot |= libc::PROT_WRITE;
+    }
+    if flags & PF_X != 0 {
+        prot |= libc::PROT_EXEC;
+    }
+    prot
+}
+
+fn around(
+    filename: *const c_char,
+    flags: c_int,
+    load: impl FnOnce() -> *mut c_void,
+) -> *mut c_void {
+    if IN_HOOK.get() {
+        return load();
+    }
+    IN_HOOK.set(true);
+    let path = (!filename.is_null()).then(|| unsafe { CStr::from_ptr(filename) });
+    let callbacks = read(&PRE_LOAD).clone();
+    for (_, callback) in &callbacks {
+        guarded(|| callback(path, flags));
+    }
+
+    let handle = load();
+
+    if !handle.is_null() {
+        patch_new_modules();
+        let callbacks = read(&POST_LOAD).clone();
+        if let Some(path) = path
+            && !callbacks.is_empty()
+            && let Some(Ok(lib)) = module::find_loaded(handle, path, None)
+                .as_ref()
+                .map(ModuleInfo::open)
+        {
+            for (_, callback) in &callbacks {
+                guarded(|| callback(path, flags, &lib));
+            }
+        }
+    }
+    IN_HOOK.set(false);
+    handle
+}
+
+/// Runs a callback from one of the `extern "C"` hooks, where unwinding would abort the
+/// process. A panicking callback is skipped, the panic hook has already reported it.
+fn guarded(callback: impl FnOnce()) {
+    let _ = panic::catch_unwind(AssertUnwindSafe(callback));
+}
+
+type LoaderDlopen = unsafe extern "C" fn(*const c_char, c_int, *const c_void) -> *mut c_void;
+type LoaderAndroidDlopenExt =
+    unsafe extern "C" fn(*const c_char, c_int, *const c_void, *const c_void) -> *mut c_void;
+type Dlopen = unsafe extern "C" fn(*const c_char, c_int) -> *mut c_void;
+type AndroidDlopenExt = unsafe extern "C" fn(*const c_char, c_int, *const c_void) -> *mut c_void;
+
+unsafe extern "C" fn hooked_loader_dlopen(
+    filename: *const c_char,
+    flags: c_int,
+    caller: *const c_void,
+) -> *mut c_void {
+    around(filename, flags, || unsafe {
+        match ORIG_LOADER_DLOPEN.load(Ordering::Acquire) {
+            0 => std::ptr::null_mut(),
+            f => std::mem::transmute::<usize, LoaderDlopen>(f)(filename, flags, caller),
+        }
+    })
+}
+
+unsafe extern "C" fn hooked_loader_android_dlopen_ext(
+    filename: *const c_char,
+    flags: c_int,
+    extinfo: *const c_void,
+    caller: *const c_void,
+) -> *mut c_void {
+    around(filename, flags, || unsafe {
+        match ORIG_LOADER_ANDROID_DLOPEN_EXT.load(Ordering::Acquire) {
+            0 => std::ptr::null_mut(),
+            f => std::mem::transmute::<usize, LoaderAndroidDlopenExt>(f)(
+                filename, flags, extinfo, caller,
+            ),
+        }
+    })
+}
+
+unsafe extern "C" fn hooked_dlopen(filename: *const c_char, flags: c_int) -> *mut c_void {
+    around(filename, flags, || unsafe {
+        match ORIG_DLOPEN.load(Ordering::Acquire) {
+            0 => std::ptr::null_mut(),
+            f => std::mem::transmute::<usize, Dlopen>(f)(filename, flags),
+        }
+    })
+}
+
+unsafe extern "C" fn hooked_android_dlopen_ext(
+    filename: *const c_char,
+    flags: c_int,
+    extinfo: *const c_void,
+) -> *mut c_void {
+    around(filename, flags, || unsafe {
+        match ORIG_ANDROID_DLOPEN_EXT.load(Ordering::Acquire) {
+            0 => std::ptr::null_mut(),
+            f => std::mem::transmute::<usize, AndroidDlopenExt>(f)(filename, flags, extinfo),
+        }
+    })
+}
+
+fn key(module: &ModuleInfo) -> (usize, usize) {
+    (module.base(), module.phdr_address())
+}
+
+fn lock() -> MutexGuard<'static, State> {
+    STATE.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn read<T>(lock: &RwLock<T>) -> std::sync::RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn write<T>(lock: &RwLock<T>) -> std::sync::RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(PoisonError::into_inner)
+}
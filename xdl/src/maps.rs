@@ -0,0 +1,50 @@
+//! Parsing of `/proc/self/maps`.
+use crate::Error;
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// One line of `/proc/self/maps`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Mapping {
+    pub start: usize,
+    pub end: usize,
+    pub readable: bool,
+    pub executable: bool,
+    pub offset: u64,
+    pub inode: u64,
+    /// Pathname or pseudo-name (`[vdso]`, `[anon:...]`), empty for anonymous mappings.
+    pub path: String,
+}
+
+impl Mapping {
+    pub(crate) fn contains(&self, address: usize) -> bool {
+        address >= self.start && address < self.end
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.splitn(6, ' ');
+        let (start, end) = fields.next()?.split_once('-')?;
+        let perms = fields.next()?;
+        let offset = fields.next()?;
+        let _dev = fields.next()?;
+        let inode = fields.next()?;
+        let path = fields.next().unwrap_or_default().trim_start();
+        Some(Self {
+            start: usize::from_str_radix(start, 16).ok()?,
+            end: usize::from_str_radix(end, 16).ok()?,
+            readable: perms.starts_with('r'),
+            executable: perms.as_bytes().get(2) == Some(&b'x'),
+            offset: u64::from_str_radix(offset, 16).ok()?,
+            inode: inode.parse().ok()?,
+            path: path.to_string(),
+        })
+    }
+}
+
+/// Reads the memory mappings of the current process.
+pub(crate) fn read() -> Result<Vec<Mapping>> {
+    let maps = std::fs::read_to_string("/proc/self/maps").map_err(|err| {
+        Error::OpeningLibraryError(format!("cannot read /proc/self/maps: {}", err))
+    })?;
+    Ok(maps.lines().filter_map(Mapping::parse).collect())
+}
@@ -0,0 +1,318 @@
+/*!
+Enumeration of the modules (ELF images) currently loaded into the process.
+
+This is a thin layer over [`xdl_iterate_phdr`](crate::raw::api::xdl_iterate_phdr). Every
+[`ModuleInfo`] is a snapshot: it copies the information it needs, so it stays valid even after
+the module has been unloaded.
+*/
+use crate::Error;
+use crate::elf::Image;
+#[cfg(target_os = "android")]
+use crate::{Library, raw::api::*};
+use std::ffi::{CStr, CString};
+use std::mem::offset_of;
+use std::ops::ControlFlow;
+use std::os::raw::{c_int, c_void};
+
+pub(crate) use crate::elf::Phdr;
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Information about one loaded module, as reported by `xdl_iterate_phdr`.
+#[derive(Debug, Clone)]
+pub struct ModuleInfo {
+    path: CString,
+    base: usize,
+    phdr: usize,
+    phdrs: Vec<Phdr>,
+    tls_modid: usize,
+}
+
+impl ModuleInfo {
+    /// Creates a snapshot with a path and a load bias only, for tests that do not load anything.
+    #[cfg(test)]
+    pub(crate) fn synthetic(path: &str, base: usize) -> Self {
+        Self {
+            path: CString::new(path).unwrap(),
+            base,
+            phdr: 0,
+            phdrs: Vec::new(),
+            tls_modid: 0,
+        }
+    }
+
+    /// Creates a snapshot from a `dl_phdr_info` structure.
+    ///
+    /// # Safety
+    /// `info` must be a valid structure of (at least) `size` bytes, as passed to an
+    /// `xdl_iterate_phdr` callback.
+    #[cfg(target_os = "android")]
+    pub(crate) unsafe fn from_raw(info: &dl_phdr_info, size: usize) -> Self {
+        unsafe {
+            let path = if info.dlpi_name.is_null() {
+                CString::default()
+            } else {
+                CStr::from_ptr(info.dlpi_name).to_owned()
+            };
+            let phdrs = if info.dlpi_phdr.is_null() {
+                Vec::new()
+            } else {
+                std::slice::from_raw_parts(info.dlpi_phdr, info.dlpi_phnum as usize).to_vec()
+            };
+            let tls_modid = if size >= offset_of!(dl_phdr_info, dlpi_tls_data) {
+                info.dlpi_tls_modid
+            } else {
+                0
+            };
+            Self {
+                path,
+                base: info.dlpi_addr as usize,
+                phdr: info.dlpi_phdr as usize,
+                phdrs,
+                tls_modid,
+            }
+        }
+    }
+
+    /// Full pathname of the module.
+    pub fn path(&self) -> &CStr {
+        &self.path
+    }
+
+    /// Last component of [`path`](Self::path).
+    pub fn basename(&self) -> &CStr {
+        let bytes = self.path.to_bytes_with_nul();
+        match bytes.iter().rposition(|&b| b == b'/') {
+            // The remainder still ends with the original NUL terminator.
+            Some(pos) => CStr::from_bytes_with_nul(&bytes[pos + 1..]).unwrap(),
+            None => &self.path,
+        }
+    }
+
+    /// Load bias of the module (`dlpi_addr`).
+    pub fn base(&self) -> usize {
+        self.base
+    }
+
+    /// Address of the module's program header table in memory.
+    pub fn phdr_address(&self) -> usize {
+        self.phdr
+    }
+
+    /// Copy of the module's program headers.
+    pub fn phdrs(&self) -> &[Phdr] {
+        &self.phdrs
+    }
+
+    /// TLS module id (`dlpi_tls_modid`), if the module has a TLS segment and the
+    /// linker reports it.
+    pub fn tls_module_id(&self) -> Option<usize> {
+        (self.tls_modid != 0).then_some(self.tls_modid)
+    }
+
+    /// Returns `true` if `addr` lies within one of the module's `PT_LOAD` segments.
+    pub fn contains(&self, addr: usize) -> bool {
+        self.phdrs.iter().any(|phdr| {
+            let start = self.base.wrapping_add(phdr.p_vaddr as usize);
+            phdr.p_type == libc::PT_LOAD && addr >= start && addr - start < phdr.p_memsz as usize
+        })
+    }
+
+    /// Returns `true` if `name` matches the basename or full path of this module.
+    pub fn matches(&self, name: &str) -> bool {
+        let path = self.path.to_bytes();
+        let name = name.as_bytes();
+        if name.contains(&b'/') {
+            path == name
+        } else {
+            self.basename().to_bytes() == name
+        }
+    }
+
+    /// Returns `true` if the module was loaded from an app's directory (`/data/app`,
+    /// `/data/data`, `/data/user`, or adopted storage).
+    pub fn is_app(&self) -> bool {
+        const PREFIXES: [&[u8]; 5] = [
+            b"/data/app/",
+            b"/data/data/",
+            b"/data/user/",
+            b"/data/user_de/",
+            b"/mnt/expand/",
+        ];
+        let path = self.path.to_bytes();
+        PREFIXES.iter().any(|prefix| path.starts_with(prefix))
+    }
+
+    /// Returns `true` if the module was loaded from a system partition or an APEX.
+    pub fn is_system(&self) -> bool {
+        const PREFIXES: [&[u8]; 6] = [
+            b"/system/",
+            b"/system_ext/",
+            b"/product/",
+            b"/vendor/",
+            b"/odm/",
+            b"/apex/",
+        ];
+        let path = self.path.to_bytes();
+        PREFIXES.iter().any(|prefix| path.starts_with(prefix))
+    }
+
+    /// Opens the module with `xdl_open2`, without calling `dlopen`.
+    ///
+    /// Fails if the module is no longer loaded.
+    #[cfg(target_os = "android")]
+    pub fn open(&self) -> Result<Library> {
+        self.open_raw().map(Library::from)
+    }
+
+    #[cfg(target_os = "android")]
+    pub(crate) fn open_raw(&self) -> Result<crate::raw::Library> {
+        let mut handle: Handle = std::ptr::null_mut();
+        iterate(XDL_FULL_PATHNAME, |info, _| {
+            if info.dlpi_addr as usize == self.base && info.dlpi_phdr as usize == self.phdr {
+                handle = unsafe { xdl_open2(info as *const _ as *mut _) };
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        unsafe { crate::raw::Library::new(handle) }.map_err(|_| {
+            Error::OpeningLibraryError(format!(
+                "`{}` is no longer loaded",
+                self.path.to_string_lossy()
+            ))
+        })
+    }
+}
+
+unsafe impl Send for ModuleInfo {}
+unsafe impl Sync for ModuleInfo {}
+
+/// Returns a snapshot of all currently loaded modules, with full pathnames.
+#[cfg(target_os = "android")]
+pub fn loaded_modules() -> Vec<ModuleInfo> {
+    let mut modules = Vec::new();
+    iterate(XDL_FULL_PATHNAME, |info, size| {
+        modules.push(unsafe { ModuleInfo::from_raw(info, size) });
+        ControlFlow::Continue(())
+    });
+    modules
+}
+
+/// Finds the loaded module that a `dlopen` handle refers to, among the modules accepted by
+/// `filter`.
+///
+/// `dlsym` on a handle searches its module before the dependencies, so a module is the one of
+/// the handle if its exported symbols resolve into it through the handle. A dependency can
+/// match as well when the module of the handle does not define the symbols tried, so matches
+/// needed by another match are dropped. Modules are read inside `xdl_iterate_phdr`, where the
+/// linker lock keeps them from being unloaded.
+///
+/// This costs up to 4 `dlsym` calls per module accepted by `filter`, so callers that know the
+/// name of the module narrow the search first (see [`find_loaded`]). A module that exports no
+/// symbol in `.dynsym` cannot be identified: `None` is returned, or one of its dependencies if
+/// their symbols are reachable through the handle.
+#[cfg(target_os = "android")]
+pub(crate) fn module_of_handle<P>(handle: Handle, filter: P) -> Option<ModuleInfo>
+where
+    P: Fn(&ModuleInfo) -> bool,
+{
+    /// Exported symbols tried per module.
+    const PROBES: usize = 4;
+    let mut matches: Vec<(ModuleInfo, Vec<String>)> = Vec::new();
+    iterate(XDL_FULL_PATHNAME, |info, size| {
+        let module = unsafe { ModuleInfo::from_raw(info, size) };
+        if !filter(&module) {
+            return ControlFlow::Continue(());
+        }
+        let image = unsafe { Image::from_memory(module.base(), module.phdrs()) };
+        let Some(dynamic) = image.dynamic() else {
+            return ControlFlow::Continue(());
+        };
+        let mut probes = image.exported_dynsyms(&dynamic).take(PROBES).peekable();
+        let resolves_here = probes.peek().is_some()
+            && probes.all(|(name, _)| {
+                let address = unsafe { libc::dlsym(handle, name.as_ptr()) };
+                module.contains(address as usize)
+            });
+        if resolves_here {
+            let needed = image.dependencies().map(|deps| deps.needed);
+            matches.push((module, needed.unwrap_or_default()));
+        }
+        ControlFlow::Continue(())
+    });
+    let needed_by_other = |module: &ModuleInfo| {
+        let basename = module.basename().to_string_lossy();
+        matches.iter().any(|(other, needed)| {
+            other.base() != module.base() && needed.iter().any(|name| name.as_str() == &*basename)
+        })
+    };
+    matches
+        .iter()
+        .find(|(module, _)| !needed_by_other(module))
+        .or(matches.first())
+        .map(|(module, _)| module.clone())
+}
+
+/// Finds the module `linker`, a handle returned by the linker, refers to: among the modules
+/// matching `name` or `fd_path` first, then among all loaded modules, e.g. when the linker
+/// resolved `name` to another path.
+///
+/// Narrowing the search by name keeps the number of `dlsym` calls of [`module_of_handle`] low;
+/// only the fallback tries every loaded module.
+#[cfg(target_os = "android")]
+pub(crate) fn find_loaded(
+    linker: Handle,
+    name: &CStr,
+    fd_path: Option<&str>,
+) -> Option<ModuleInfo> {
+    let name = name.to_string_lossy();
+    let basename = name.rsplit('/').next().unwrap_or(&name);
+    module_of_handle(linker, |module| {
+        module.matches(basename)
+            || fd_path.is_some_and(|fd_path| module.path().to_string_lossy().starts_with(fd_path))
+    })
+    .or_else(|| module_of_handle(linker, |_| true))
+}
+
+/// Returns the linker's load and unload counters (`dlpi_adds`, `dlpi_subs`).
+///
+/// These are only reported since Android 11; `None` is returned on older systems.
+#[cfg(target_os = "android")]
+pub(crate) fn load_counters() -> Option<(u64, u64)> {
+    let mut counters = None;
+    iterate(XDL_DEFAULT, |info, size| {
+        if size >= offset_of!(dl_phdr_info, dlpi_tls_modid) {
+            counters = Some((info.dlpi_adds, info.dlpi_subs));
+        }
+        ControlFlow::Break(())
+    });
+    counters
+}
+
+/// Calls `f` for every loaded module until it returns [`ControlFlow::Break`].
+#[cfg(target_os = "android")]
+pub(crate) fn iterate<F>(flags: c_int, mut f: F)
+where
+    F: FnMut(&dl_phdr_info, usize) -> ControlFlow<()>,
+{
+    unsafe extern "C" fn callback<F>(
+        info: *mut dl_phdr_info,
+        size: usize,
+        data: *mut c_void,
+    ) -> c_int
+    where
+        F: FnMut(&dl_phdr_info, usize) -> ControlFlow<()>,
+    {
+        unsafe {
+            let f = &mut *(data as *mut F);
+            match f(&*info, size) {
+                ControlFlow::Continue(()) => 0,
+                ControlFlow::Break(()) => 1,
+            }
+        }
+    }
+    unsafe {
+        xdl_iterate_phdr(callback::<F>, &mut f as *mut F as *mut c_void, flags);
+    }
+}
@@ -0,0 +1,349 @@
+/*!
+Build-ID keyed database of function and variable offsets.
+
+Some functions are not exported at all, and can only be located by their offset in one exact
+build of a library. An [`OffsetDatabase`] maps `(library, build ID, architecture)` to a table of
+named offsets; [`Library::resolve_from_db`] looks up the running build and returns a bound
+[`Symbol`].
+
+Offsets are virtual addresses relative to the load bias, i.e. the symbol values printed by
+`nm` or `readelf -s` for the reference build.
+
+The database is stored as TOML or JSON:
+
+```toml
+[[library]]
+name = "libgame.so"
+build_id = "5f2c6b3d0e8f4a51b5b2c8d9e6f7a1b2c3d4e5f6"
+arch = "arm64"
+
+[library.offsets]
+update_player = 0x1a2b30
+damage_table = 0x3c0010
+```
+
+JSON uses the same layout (`{"library": [...]}`); offsets may be written as numbers or as
+hexadecimal strings.
+
+Entries can be generated from a symbolized (unstripped) reference build with
+[`OffsetEntry::from_reference`]. Only the lookup in a running process,
+[`Library::resolve_from_db`], is specific to Android: the database itself can be generated and
+edited on any host.
+
+```no_run
+# #[cfg(target_os = "android")]
+# fn main() {
+use android_xdl::Library;
+use android_xdl::offsets::OffsetDatabase;
+
+let db = OffsetDatabase::load("/data/local/tmp/offsets.toml").unwrap();
+let lib = Library::open(c"libgame.so").unwrap();
+let update_player = lib
+    .resolve_from_db::<unsafe extern "C" fn(i32)>(&db, "update_player")
+    .unwrap();
+unsafe { update_player(1) };
+# }
+# #[cfg(not(target_os = "android"))]
+# fn main() {}
+```
+*/
+use crate::Error;
+use crate::elf::{BuildId, EM_386, EM_AARCH64, EM_ARM, EM_X86_64, ElfFile};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+#[cfg(target_os = "android")]
+use crate::elf::Image;
+#[cfg(target_os = "android")]
+use crate::raw::assert_type_size;
+#[cfg(target_os = "android")]
+use crate::{Library, Symbol};
+#[cfg(target_os = "android")]
+use std::{ffi::CStr, mem::transmute_copy, os::raw::c_void};
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// CPU architecture of a database entry, named after the Android ABI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Arch {
+    /// `armeabi-v7a`
+    #[serde(rename = "arm", alias = "armeabi-v7a")]
+    Arm,
+    /// `arm64-v8a`
+    #[serde(rename = "arm64", alias = "arm64-v8a", alias = "aarch64")]
+    Arm64,
+    /// `x86`
+    #[serde(rename = "x86", alias = "i686")]
+    X86,
+    /// `x86_64`
+    #[serde(rename = "x86_64")]
+    X86_64,
+}
+
+impl Arch {
+    /// The architecture of the current process.
+    pub const fn current() -> Self {
+        #[cfg(target_arch = "arm")]
+        return Arch::Arm;
+        #[cfg(target_arch = "aarch64")]
+        return Arch::Arm64;
+        #[cfg(target_arch = "x86")]
+        return Arch::X86;
+        #[cfg(target_arch = "x86_64")]
+        return Arch::X86_64;
+    }
+
+    /// Maps an ELF `e_machine` to the architecture.
+    pub fn from_machine(machine: u16) -> Option<Self> {
+        match machine {
+            EM_ARM => Some(Arch::Arm),
+            EM_AARCH64 => Some(Arch::Arm64),
+            EM_386 => Some(Arch::X86),
+            EM_X86_64 => Some(Arch::X86_64),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Arch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Arch::Arm => "arm",
+            Arch::Arm64 => "arm64",
+            Arch::X86 => "x86",
+            Arch::X86_64 => "x86_64",
+        })
+    }
+}
+
+/// The offsets of one build of one library.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OffsetEntry {
+    /// Basename (or full path) of the library.
+    pub name: String,
+    /// Build ID of the library, see [`Library::build_id`].
+    #[serde(serialize_with = "serialize_build_id")]
+    #[serde(deserialize_with = "deserialize_build_id")]
+    pub build_id: BuildId,
+    /// Architecture of the library.
+    pub arch: Arch,
+    /// Offsets relative to the load bias, by name.
+    #[serde(default, deserialize_with = "deserialize_offsets")]
+    pub offsets: BTreeMap<String, u64>,
+}
+
+impl OffsetEntry {
+    /// Returns the offset of `name`.
+    pub fn offset(&self, name: &str) -> Option<u64> {
+        self.offsets.get(name).copied()
+    }
+
+    /// Generates an entry from a symbolized reference build of a library.
+    ///
+    /// Every name in `symbols` is looked up in `.symtab` and then `.dynsym` of `file`, which
+    /// must have a build ID. The entry is named after the file's `DT_SONAME`, or `name` if given.
+    ///
+    /// ```no_run
+    /// use android_xdl::elf::ElfFile;
+    /// use android_xdl::offsets::{OffsetDatabase, OffsetEntry};
+    ///
+    /// let file = ElfFile::open("symbols/libgame.so").unwrap();
+    /// let entry = OffsetEntry::from_reference(&file, None, ["update_player"]).unwrap();
+    /// let mut db = OffsetDatabase::new();
+    /// db.insert(entry);
+    /// std::fs::write("offsets.toml", db.to_toml().unwrap()).unwrap();
+    /// ```
+    pub fn from_reference<'a, I>(file: &ElfFile, name: Option<&str>, symbols: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let build_id = file
+            .build_id()
+            .ok_or_else(|| Error::InvalidElf("the reference build has no build ID".to_string()))?;
+        let arch = Arch::from_machine(file.machine())
+            .ok_or_else(|| Error::InvalidElf(format!("unsupported machine {}", file.machine())))?;
+        let name = match name {
+            Some(name) => name.to_string(),
+            None => file.dependencies()?.soname.ok_or_else(|| {
+                Error::InvalidElf("the reference build has no DT_SONAME".to_string())
+            })?,
+        };
+        let offsets = symbols
+            .into_iter()
+            .map(|symbol| {
+                file.find_symbol(symbol)
+                    .map(|sym| (symbol.to_string(), sym.value))
+                    .ok_or_else(|| Error::SymbolNotFound(symbol.to_string()))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            name,
+            build_id,
+            arch,
+            offsets,
+        })
+    }
+
+    fn matches(&self, path: &str, build_id: &BuildId, arch: Arch) -> bool {
+        let name_matches = if self.name.contains('/') {
+            path == self.name
+        } else {
+            path.rsplit('/').next() == Some(self.name.as_str())
+        };
+        name_matches && self.build_id == *build_id && self.arch == arch
+    }
+}
+
+/// A set of [`OffsetEntry`], see the [module documentation](self).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OffsetDatabase {
+    #[serde(default, rename = "library")]
+    entries: Vec<OffsetEntry>,
+}
+
+impl OffsetDatabase {
+    /// Creates an empty database.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads a database file, parsed as JSON if its extension is `.json` and as TOML otherwise.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|err| {
+            Error::OffsetDatabaseError(format!("cannot read `{}`: {}", path.display(), err))
+        })?;
+        match path.extension() {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Self::from_json(&text),
+            _ => Self::from_toml(&text),
+        }
+    }
+
+    /// Parses a database in TOML format.
+    pub fn from_toml(text: &str) -> Result<Self> {
+        toml::from_str(text).map_err(|err| Error::OffsetDatabaseError(err.to_string()))
+    }
+
+    /// Parses a database in JSON format.
+    pub fn from_json(text: &str) -> Result<Self> {
+        serde_json::from_str(text).map_err(|err| Error::OffsetDatabaseError(err.to_string()))
+    }
+
+    /// Serializes the database to TOML.
+    pub fn to_toml(&self) -> Result<String> {
+        toml::to_string_pretty(self).map_err(|err| Error::OffsetDatabaseError(err.to_string()))
+    }
+
+    /// Serializes the database to JSON.
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|err| Error::OffsetDatabaseError(err.to_string()))
+    }
+
+    /// All entries of the database.
+    pub fn entries(&self) -> &[OffsetEntry] {
+        &self.entries
+    }
+
+    /// Adds an entry, merging its offsets into an existing entry for the same build.
+    pub fn insert(&mut self, entry: OffsetEntry) {
+        let existing = self
+            .entries
+            .iter_mut()
+            .find(|e| e.name == entry.name && e.build_id == entry.build_id && e.arch == entry.arch);
+        match existing {
+            Some(existing) => existing.offsets.extend(entry.offsets),
+            None => self.entries.push(entry),
+        }
+    }
+
+    /// Finds the entry of a build. `library` is the path or basename of the library.
+    pub fn find(&self, library: &str, build_id: &BuildId, arch: Arch) -> Option<&OffsetEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.matches(library, build_id, arch))
+    }
+}
+
+#[cfg(target_os = "android")]
+impl Library {
+    /// Resolves a symbol by its offset in the [`OffsetDatabase`].
+    ///
+    /// The library is identified by its path, its build ID and the current architecture.
+    /// Returns [`Error::UnknownBuild`] if the running build is not in the database, and
+    /// [`Error::SymbolNotFound`] if the build is known but has no offset for `name`.
+    pub fn resolve_from_db<T>(&self, db: &OffsetDatabase, name: &str) -> Result<Symbol<'_, T>> {
+        assert_type_size::<T>();
+        let info = self.info()?;
+        let path = if info.dli_fname.is_null() {
+            String::new()
+        } else {
+            unsafe { CStr::from_ptr(info.dli_fname) }
+                .to_string_lossy()
+                .into_owned()
+        };
+        let image = unsafe { Image::from_library(self)? };
+        let build_id = image
+            .build_id()
+            .ok_or_else(|| Error::UnknownBuild(format!("`{}` has no build ID", path)))?;
+        let arch = Arch::current();
+        let entry = db.find(&path, &build_id, arch).ok_or_else(|| {
+            Error::UnknownBuild(format!("`{}` (build ID {}, {})", path, build_id, arch))
+        })?;
+        let offset = entry
+            .offset(name)
+            .ok_or_else(|| Error::SymbolNotFound(name.to_string()))?;
+        if image.load_segment(offset).is_none() {
+            return Err(Error::OffsetDatabaseError(format!(
+                "offset {:#x} of `{}` lies outside of `{}`",
+                offset, name, path
+            )));
+        }
+        let address = (info.dli_fbase as usize).wrapping_add(offset as usize) as *mut c_void;
+        Ok(Symbol::new(unsafe { transmute_copy(&address) }))
+    }
+}
+
+fn serialize_build_id<S: Serializer>(
+    build_id: &BuildId,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_str(&build_id.to_hex())
+}
+
+fn deserialize_build_id<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<BuildId, D::Error> {
+    let hex = String::deserialize(deserializer)?;
+    hex.parse().map_err(D::Error::custom)
+}
+
+fn deserialize_offsets<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<BTreeMap<String, u64>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Offset {
+        Number(u64),
+        Text(String),
+    }
+
+    BTreeMap::<String, Offset>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(name, offset)| {
+            let offset = match offset {
+                Offset::Number(offset) => offset,
+                Offset::Text(text) => {
+                    let digits = text.trim_start_matches("0x").trim_start_matches("0X");
+                    u64::from_str_radix(digits, 16).map_err(|_| {
+                        D::Error::custom(format!("invalid offset `{}` for `{}`", text, name))
+                    })?
+                }
+            };
+            Ok((name, offset))
+        })
+        .collect()
+}
@@ -205,3 +205,18 @@ unsafe extern "C" {
     /// `handle` must be valid and `info` must point to writable memory of appropriate size.
     pub fn xdl_info(handle: Handle, request: c_int, info: *mut c_void) -> c_int;
 }
+
+// Internal functions of xDL, declared in `xdl_lzma.h`.
+#[link(name = "xdl")]
+unsafe extern "C" {
+    /// Decompresses the xz stream `src`, the content of `.gnu_debugdata`.
+    ///
+    /// Returns `0` on success, with `*dst` pointing to `*dst_size` bytes allocated with
+    /// `malloc`, which the caller must `free`.
+    pub(crate) fn xdl_lzma_decompress(
+        src: *mut u8,
+        src_size: usize,
+        dst: *mut *mut u8,
+        dst_size: *mut usize,
+    ) -> c_int;
+}
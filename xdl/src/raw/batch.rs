@@ -0,0 +1,152 @@
+use super::{Library, SymbolHash, SymbolKind, assert_type_size, call_resolver};
+use crate::Error;
+use crate::elf::ElfSymbol;
+use std::ffi::CStr;
+use std::mem::transmute_copy;
+use std::os::raw::c_void;
+use std::ptr::NonNull;
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// A symbol to look up with [`Library::resolve_batch`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SymbolRequest<'a> {
+    pub name: &'a CStr,
+    /// Look the symbol up in `.symtab`, like [`Library::debug_symbol`].
+    pub debug: bool,
+    /// Hashes of the name computed in advance, see [`Library::symbol_prehashed`].
+    pub hash: Option<SymbolHash>,
+}
+
+impl<'a> SymbolRequest<'a> {
+    /// A symbol of `.dynsym`, like [`Library::symbol`].
+    pub const fn new(name: &'a CStr) -> Self {
+        Self {
+            name,
+            debug: false,
+            hash: None,
+        }
+    }
+
+    /// A symbol of `.dynsym` with the hashes of its name computed in advance.
+    pub const fn prehashed(name: &'a CStr, gnu: u32, sysv: u32) -> Self {
+        Self {
+            name,
+            debug: false,
+            hash: Some(SymbolHash { gnu, sysv }),
+        }
+    }
+
+    /// A symbol of `.symtab`, like [`Library::debug_symbol`].
+    pub const fn debug(name: &'a CStr) -> Self {
+        Self {
+            name,
+            debug: true,
+            hash: None,
+        }
+    }
+}
+
+/// The address and size of a symbol found by [`Library::resolve_batch`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResolvedSymbol {
+    pub address: NonNull<c_void>,
+    pub size: usize,
+}
+
+impl Library {
+    /// Looks up many symbols at once, returning one result per request, in order.
+    ///
+    /// The program headers and dynamic section of the library are parsed once per library
+    /// for all `.dynsym` lookups, and debug lookups use the `.symtab` and `.gnu_debugdata`
+    /// symbols of the library, which are also read and indexed once per library. As with
+    /// `xdl_dsym`, the first definition of a name is returned. Symbols the batch can not find
+    /// are looked up with `xdl_sym` or `xdl_dsym`.
+    ///
+    /// The resolvers of indirect functions (`STT_GNU_IFUNC`) are run, as with
+    /// [`Library::symbol`](crate::Library::symbol), so that `#[derive(NativeBridge)]` binds the
+    /// implementation rather than the resolver.
+    pub fn resolve_batch(&self, requests: &[SymbolRequest]) -> Vec<Result<ResolvedSymbol>> {
+        requests
+            .iter()
+            .map(|request| self.resolve(request))
+            .collect()
+    }
+
+    /// Looks up one symbol like [`resolve_batch`](Self::resolve_batch): a single search of
+    /// `.dynsym` or of the cached debugging symbols yields both the address and the type, so
+    /// that indirect functions are resolved without a second lookup.
+    pub(crate) fn resolve(&self, request: &SymbolRequest) -> Result<ResolvedSymbol> {
+        let at = |bias: usize, symbol: &ElfSymbol| {
+            let mut address = bias.wrapping_add(symbol.value as usize) as *mut c_void;
+            if symbol.is_ifunc() {
+                address = unsafe { call_resolver(address) } as *mut c_void;
+            }
+            NonNull::new(address).map(|address| ResolvedSymbol {
+                address,
+                size: symbol.size as usize,
+            })
+        };
+        let found = self.image().ok().and_then(|(image, dynamic)| {
+            let bias = image.bias()?;
+            if request.debug {
+                let symbol = self.debug_symbols()?.get(request.name.to_bytes())?;
+                return at(bias, symbol);
+            }
+            let sym = match request.hash {
+                Some(hash) => {
+                    image.lookup_dynsym_hashed(dynamic, request.name, hash.gnu, hash.sysv)
+                }
+                None => image.lookup_dynsym(dynamic, request.name),
+            }?;
+            at(bias, &ElfSymbol::new(String::new(), &sym))
+        });
+        found.map_or_else(|| self.resolve_one(request), Ok)
+    }
+
+    /// Converts an entry returned by [`resolve_batch`](Self::resolve_batch) to `T`, like
+    /// [`symbol`](Self::symbol).
+    ///
+    /// # Safety
+    /// `T` must match the type of the symbol, as with [`symbol`](Self::symbol).
+    pub unsafe fn bind_resolved<T>(
+        &self,
+        resolved: &Result<ResolvedSymbol>,
+        name: &CStr,
+    ) -> Result<T> {
+        assert_type_size::<T>();
+        match resolved {
+            Ok(symbol) => Ok(unsafe { transmute_copy(&symbol.address.as_ptr()) }),
+            Err(_) => Err(Error::SymbolNotFound(name.to_string_lossy().to_string())),
+        }
+    }
+
+    /// Like [`bind_resolved`](Self::bind_resolved), but first checks the `.dynsym` entry of
+    /// the symbol against `T`, like [`symbol_checked`](Self::symbol_checked).
+    ///
+    /// # Safety
+    /// See [`symbol_checked`](Self::symbol_checked).
+    pub unsafe fn bind_resolved_checked<T: SymbolKind>(
+        &self,
+        resolved: &Result<ResolvedSymbol>,
+        name: &CStr,
+    ) -> Result<T> {
+        self.check_kind::<T>(name)?;
+        unsafe { self.bind_resolved(resolved, name) }
+    }
+
+    fn resolve_one(&self, request: &SymbolRequest) -> Result<ResolvedSymbol> {
+        let mut size = 0;
+        let address = unsafe {
+            if request.debug {
+                self.debug_symbol::<*mut c_void>(request.name, Some(&mut size))?
+            } else {
+                self.symbol::<*mut c_void>(request.name, Some(&mut size))?
+            }
+        };
+        let address = self.resolve_ifunc(request.name, request.debug, address)?;
+        let address = NonNull::new(address)
+            .ok_or_else(|| Error::SymbolNotFound(request.name.to_string_lossy().to_string()))?;
+        Ok(ResolvedSymbol { address, size })
+    }
+}
@@ -0,0 +1,101 @@
+/*!
+Bindings to bionic's [`android_dlopen_ext`](https://developer.android.com/ndk/reference/group/libdl),
+declared in `<android/dlext.h>`.
+
+`android_dlopen_ext` only exists since Android 5.0 (API level 21), so it is looked up at run time
+with [`android_dlopen_ext`] instead of being linked against.
+*/
+#![allow(non_camel_case_types)]
+use std::ffi::CStr;
+use std::os::raw::*;
+use std::sync::OnceLock;
+
+/// Opaque linker namespace, see `android_create_namespace`.
+#[repr(C)]
+pub struct android_namespace_t {
+    _private: [u8; 0],
+}
+
+/// Load the library at the address given by `reserved_addr` and `reserved_size`.
+pub const ANDROID_DLEXT_RESERVED_ADDRESS: u64 = 0x1;
+/// Like [`ANDROID_DLEXT_RESERVED_ADDRESS`], but fall back to any address if the library does
+/// not fit.
+pub const ANDROID_DLEXT_RESERVED_ADDRESS_HINT: u64 = 0x2;
+/// Write the GNU RELRO section of the library to `relro_fd` after relocation.
+pub const ANDROID_DLEXT_WRITE_RELRO: u64 = 0x4;
+/// Map the GNU RELRO section read from `relro_fd` instead of relocating it.
+pub const ANDROID_DLEXT_USE_RELRO: u64 = 0x8;
+/// Read the library from `library_fd` instead of opening it by name.
+pub const ANDROID_DLEXT_USE_LIBRARY_FD: u64 = 0x10;
+/// Read the library at `library_fd_offset` of `library_fd` (e.g. a stored APK entry).
+pub const ANDROID_DLEXT_USE_LIBRARY_FD_OFFSET: u64 = 0x20;
+/// Load the library even if another one with the same name (or inode) is already loaded.
+pub const ANDROID_DLEXT_FORCE_LOAD: u64 = 0x40;
+/// Load the library at its fixed `p_vaddr` within the reserved region.
+pub const ANDROID_DLEXT_LOAD_AT_FIXED_ADDRESS: u64 = 0x100;
+/// Load the library into `library_namespace` instead of the caller's namespace.
+pub const ANDROID_DLEXT_USE_NAMESPACE: u64 = 0x200;
+/// Also load the dependencies of the library into the reserved region.
+pub const ANDROID_DLEXT_RESERVED_ADDRESS_RECURSIVE: u64 = 0x400;
+
+/// Extended `dlopen` options, see `<android/dlext.h>`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct android_dlextinfo {
+    /// A bitmask of `ANDROID_DLEXT_*` flags.
+    pub flags: u64,
+    /// Start of the reserved region.
+    pub reserved_addr: *mut c_void,
+    /// Size of the reserved region.
+    pub reserved_size: usize,
+    /// File descriptor for [`ANDROID_DLEXT_WRITE_RELRO`] and [`ANDROID_DLEXT_USE_RELRO`].
+    pub relro_fd: c_int,
+    /// File descriptor for [`ANDROID_DLEXT_USE_LIBRARY_FD`].
+    pub library_fd: c_int,
+    /// Offset for [`ANDROID_DLEXT_USE_LIBRARY_FD_OFFSET`].
+    pub library_fd_offset: i64,
+    /// Namespace for [`ANDROID_DLEXT_USE_NAMESPACE`].
+    pub library_namespace: *mut android_namespace_t,
+}
+
+impl Default for android_dlextinfo {
+    fn default() -> Self {
+        Self {
+            flags: 0,
+            reserved_addr: std::ptr::null_mut(),
+            reserved_size: 0,
+            relro_fd: -1,
+            library_fd: -1,
+            library_fd_offset: 0,
+            library_namespace: std::ptr::null_mut(),
+        }
+    }
+}
+
+/// Signature of `android_dlopen_ext`.
+pub type android_dlopen_ext_t = unsafe extern "C" fn(
+    filename: *const c_char,
+    flags: c_int,
+    extinfo: *const android_dlextinfo,
+) -> *mut c_void;
+
+/// Returns `android_dlopen_ext`, or `None` before Android 5.0.
+pub fn android_dlopen_ext() -> Option<android_dlopen_ext_t> {
+    static FUNCTION: OnceLock<usize> = OnceLock::new();
+    let address = *FUNCTION.get_or_init(|| unsafe {
+        libc::dlsym(libc::RTLD_DEFAULT, c"android_dlopen_ext".as_ptr()) as usize
+    });
+    (address != 0).then(|| unsafe { std::mem::transmute::<usize, android_dlopen_ext_t>(address) })
+}
+
+/// Returns the message of the last `dlopen`/`dlsym` error of the thread.
+pub(crate) fn dlerror() -> String {
+    let error = unsafe { libc::dlerror() };
+    if error.is_null() {
+        "unknown error".to_string()
+    } else {
+        unsafe { CStr::from_ptr(error) }
+            .to_string_lossy()
+            .into_owned()
+    }
+}
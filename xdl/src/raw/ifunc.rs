@@ -0,0 +1,85 @@
+use super::Library;
+use crate::Error;
+use crate::elf::ElfSymbol;
+use std::ffi::CStr;
+use std::os::raw::c_void;
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// `__ifunc_arg_t`, the second argument of a resolver on arm64.
+#[cfg(target_arch = "aarch64")]
+#[repr(C)]
+struct IFuncArg {
+    size: usize,
+    hwcap: u64,
+    hwcap2: u64,
+}
+
+/// Calls a resolver like bionic's `call_ifunc_resolver`.
+#[cfg(target_arch = "aarch64")]
+pub(crate) unsafe fn call_resolver(resolver: *const c_void) -> *const c_void {
+    /// Tells the resolver that the second argument is an `__ifunc_arg_t`.
+    const IFUNC_ARG_HWCAP: u64 = 1 << 62;
+    type Resolver = unsafe extern "C" fn(u64, *const IFuncArg) -> *const c_void;
+    unsafe {
+        let arg = IFuncArg {
+            size: size_of::<IFuncArg>(),
+            hwcap: libc::getauxval(libc::AT_HWCAP) as u64,
+            hwcap2: libc::getauxval(libc::AT_HWCAP2) as u64,
+        };
+        let resolver = std::mem::transmute::<*const c_void, Resolver>(resolver);
+        resolver(arg.hwcap | IFUNC_ARG_HWCAP, &arg)
+    }
+}
+
+/// Calls a resolver like bionic's `call_ifunc_resolver`.
+#[cfg(target_arch = "arm")]
+pub(crate) unsafe fn call_resolver(resolver: *const c_void) -> *const c_void {
+    type Resolver = unsafe extern "C" fn(std::os::raw::c_ulong) -> *const c_void;
+    unsafe {
+        let resolver = std::mem::transmute::<*const c_void, Resolver>(resolver);
+        resolver(libc::getauxval(libc::AT_HWCAP))
+    }
+}
+
+/// Calls a resolver like bionic's `call_ifunc_resolver`.
+#[cfg(not(any(target_arch = "aarch64", target_arch = "arm")))]
+pub(crate) unsafe fn call_resolver(resolver: *const c_void) -> *const c_void {
+    type Resolver = unsafe extern "C" fn() -> *const c_void;
+    unsafe { std::mem::transmute::<*const c_void, Resolver>(resolver)() }
+}
+
+impl Library {
+    /// Returns `true` if `name` is an indirect function (`STT_GNU_IFUNC`) in `.dynsym` or,
+    /// with `debug`, in the debugging symbols.
+    pub(crate) fn is_ifunc(&self, name: &CStr, debug: bool) -> bool {
+        if debug {
+            self.debug_symbols()
+                .and_then(|symbols| symbols.get(name.to_bytes()))
+                .is_some_and(ElfSymbol::is_ifunc)
+        } else {
+            self.elf_symbol(name).is_ok_and(|symbol| symbol.is_ifunc())
+        }
+    }
+
+    /// Runs the resolver at `address` if `name` is an indirect function, as `dlsym` does, and
+    /// returns the implementation it selects. Other symbols are returned as is.
+    pub(crate) fn resolve_ifunc(
+        &self,
+        name: &CStr,
+        debug: bool,
+        address: *mut c_void,
+    ) -> Result<*mut c_void> {
+        if !self.is_ifunc(name, debug) {
+            return Ok(address);
+        }
+        let resolved = unsafe { call_resolver(address) };
+        if resolved.is_null() {
+            return Err(Error::InvalidSymbol(format!(
+                "the resolver of `{}` returned null",
+                name.to_string_lossy()
+            )));
+        }
+        Ok(resolved as *mut c_void)
+    }
+}
@@ -0,0 +1,231 @@
+use super::Library;
+use crate::Error;
+use crate::elf::ElfSymbol;
+use std::ffi::CStr;
+use std::mem::size_of;
+use std::os::raw::c_void;
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// A type that a symbol can be bound as, checked against the `.dynsym` entry of the symbol by
+/// [`Library::symbol_checked`].
+///
+/// Implemented for function pointers (`STT_FUNC` or `STT_GNU_IFUNC`), whether `extern "C"`,
+/// plain `fn` or `unsafe fn`, and for references to data (`STT_OBJECT`, at least
+/// `size_of::<T>()` bytes). Raw pointers accept both: a data object of at least
+/// `size_of::<T>()` bytes, or a function, e.g. `*const c_void` for its address. Symbols without
+/// a type (`STT_NOTYPE`, e.g. defined in assembly) are accepted by all of
+/// them; thread-local symbols (`STT_TLS`) by none.
+pub trait SymbolKind {
+    /// Returns a description of the mismatch if `symbol` can not be bound as `Self`.
+    fn check(symbol: &ElfSymbol) -> std::result::Result<(), String>;
+}
+
+/// Checks a symbol bound as a function.
+fn check_function(symbol: &ElfSymbol) -> std::result::Result<(), String> {
+    if symbol.is_function() || symbol.is_ifunc() || symbol.is_untyped() {
+        Ok(())
+    } else {
+        Err(format!("expected a function, found {}", describe(symbol)))
+    }
+}
+
+/// Checks a symbol bound as a reference to a `T`.
+fn check_data<T>(symbol: &ElfSymbol) -> std::result::Result<(), String> {
+    if !symbol.is_object() && !symbol.is_untyped() {
+        return Err(format!(
+            "expected a data object, found {}",
+            describe(symbol)
+        ));
+    }
+    check_size::<T>(symbol)
+}
+
+/// Checks a symbol bound as a raw pointer, to a `T` or to code.
+fn check_pointer<T>(symbol: &ElfSymbol) -> std::result::Result<(), String> {
+    if symbol.is_function() || symbol.is_ifunc() {
+        return Ok(());
+    }
+    if !symbol.is_object() && !symbol.is_untyped() {
+        return Err(format!(
+            "expected a data object or a function, found {}",
+            describe(symbol)
+        ));
+    }
+    check_size::<T>(symbol)
+}
+
+/// Checks that an object is large enough for a `T`.
+fn check_size<T>(symbol: &ElfSymbol) -> std::result::Result<(), String> {
+    if symbol.size != 0 && symbol.size < size_of::<T>() as u64 {
+        return Err(format!(
+            "the object is {} bytes, smaller than the {} bytes of `{}`",
+            symbol.size,
+            size_of::<T>(),
+            std::any::type_name::<T>()
+        ));
+    }
+    Ok(())
+}
+
+fn describe(symbol: &ElfSymbol) -> &'static str {
+    if symbol.is_function() || symbol.is_ifunc() {
+        "a function"
+    } else if symbol.is_tls() {
+        "a thread-local variable"
+    } else if symbol.is_object() {
+        "a data object"
+    } else {
+        "a symbol of another type"
+    }
+}
+
+impl<T: SymbolKind> SymbolKind for Option<T> {
+    fn check(symbol: &ElfSymbol) -> std::result::Result<(), String> {
+        T::check(symbol)
+    }
+}
+
+impl<T> SymbolKind for &T {
+    fn check(symbol: &ElfSymbol) -> std::result::Result<(), String> {
+        check_data::<T>(symbol)
+    }
+}
+
+impl<T> SymbolKind for &mut T {
+    fn check(symbol: &ElfSymbol) -> std::result::Result<(), String> {
+        check_data::<T>(symbol)
+    }
+}
+
+impl<T> SymbolKind for *const T {
+    fn check(symbol: &ElfSymbol) -> std::result::Result<(), String> {
+        check_pointer::<T>(symbol)
+    }
+}
+
+impl<T> SymbolKind for *mut T {
+    fn check(symbol: &ElfSymbol) -> std::result::Result<(), String> {
+        check_pointer::<T>(symbol)
+    }
+}
+
+macro_rules! function_kind {
+    ($($arg:ident),*) => {
+        impl<R, $($arg),*> SymbolKind for extern "C" fn($($arg),*) -> R {
+            fn check(symbol: &ElfSymbol) -> std::result::Result<(), String> {
+                check_function(symbol)
+            }
+        }
+
+        impl<R, $($arg),*> SymbolKind for unsafe extern "C" fn($($arg),*) -> R {
+            fn check(symbol: &ElfSymbol) -> std::result::Result<(), String> {
+                check_function(symbol)
+            }
+        }
+
+        impl<R, $($arg),*> SymbolKind for fn($($arg),*) -> R {
+            fn check(symbol: &ElfSymbol) -> std::result::Result<(), String> {
+                check_function(symbol)
+            }
+        }
+
+        impl<R, $($arg),*> SymbolKind for unsafe fn($($arg),*) -> R {
+            fn check(symbol: &ElfSymbol) -> std::result::Result<(), String> {
+                check_function(symbol)
+            }
+        }
+    };
+}
+
+macro_rules! variadic_function_kind {
+    ($($arg:ident),+) => {
+        impl<R, $($arg),+> SymbolKind for extern "C" fn($($arg),+, ...) -> R {
+            fn check(symbol: &ElfSymbol) -> std::result::Result<(), String> {
+                check_function(symbol)
+            }
+        }
+
+        impl<R, $($arg),+> SymbolKind for unsafe extern "C" fn($($arg),+, ...) -> R {
+            fn check(symbol: &ElfSymbol) -> std::result::Result<(), String> {
+                check_function(symbol)
+            }
+        }
+    };
+}
+
+function_kind!();
+function_kind!(A);
+function_kind!(A, B);
+function_kind!(A, B, C);
+function_kind!(A, B, C, D);
+function_kind!(A, B, C, D, E);
+function_kind!(A, B, C, D, E, F);
+function_kind!(A, B, C, D, E, F, G);
+function_kind!(A, B, C, D, E, F, G, H);
+function_kind!(A, B, C, D, E, F, G, H, I);
+function_kind!(A, B, C, D, E, F, G, H, I, J);
+function_kind!(A, B, C, D, E, F, G, H, I, J, K);
+function_kind!(A, B, C, D, E, F, G, H, I, J, K, L);
+
+variadic_function_kind!(A);
+variadic_function_kind!(A, B);
+variadic_function_kind!(A, B, C);
+variadic_function_kind!(A, B, C, D);
+variadic_function_kind!(A, B, C, D, E);
+variadic_function_kind!(A, B, C, D, E, F);
+
+impl Library {
+    /// Like [`symbol`](Self::symbol), but first checks the `.dynsym` entry of the symbol
+    /// against `T`, see [`SymbolKind`].
+    ///
+    /// Fails with [`Error::InvalidSymbol`] if the type or size of the symbol does not match.
+    /// As with [`symbol`](Self::symbol), the resolver of an indirect function is returned.
+    ///
+    /// # Safety
+    /// The check only covers the type and size of the symbol: the signature of a function or
+    /// the layout of an object are still trusted, as with [`symbol`](Self::symbol).
+    pub unsafe fn symbol_checked<T: SymbolKind>(
+        &self,
+        name: &CStr,
+        symbol_size: Option<&mut usize>,
+    ) -> Result<T> {
+        self.check_kind::<T>(name)?;
+        unsafe { self.symbol(name, symbol_size) }
+    }
+
+    /// Checks the `.dynsym` entry of `name` against `T`, see [`SymbolKind`].
+    pub(crate) fn check_kind<T: SymbolKind>(&self, name: &CStr) -> Result<()> {
+        let symbol = self.elf_symbol(name)?;
+        T::check(&symbol)
+            .map_err(|msg| Error::InvalidSymbol(format!("`{}`: {}", name.to_string_lossy(), msg)))
+    }
+
+    /// Checks that the `size` bytes at `address`, e.g. a variable of the library, lie within a
+    /// writable segment, outside of `.rodata` and the RELRO sections.
+    ///
+    /// Fails with [`Error::InvalidSymbol`] otherwise. `#[derive(NativeBridge)]` checks
+    /// `&mut T` and atomic fields with it.
+    pub fn check_writable(&self, address: *const c_void, size: usize, name: &CStr) -> Result<()> {
+        let (image, _) = self.image()?;
+        let vaddr = (address as usize).wrapping_sub(image.bias().unwrap_or_default());
+        if image.is_writable(vaddr as u64, size as u64) {
+            Ok(())
+        } else {
+            Err(Error::InvalidSymbol(format!(
+                "`{}`: {:p} is not within a writable segment",
+                name.to_string_lossy(),
+                address
+            )))
+        }
+    }
+
+    /// Looks up the `.dynsym` entry of a defined symbol.
+    pub fn elf_symbol(&self, name: &CStr) -> Result<ElfSymbol> {
+        let (image, dynamic) = self.image()?;
+        image
+            .lookup_dynsym(dynamic, name)
+            .map(|sym| ElfSymbol::new(name.to_string_lossy().into_owned(), &sym))
+            .ok_or_else(|| Error::SymbolNotFound(name.to_string_lossy().to_string()))
+    }
+}
@@ -1,9 +1,17 @@
 use super::api::*;
+use super::dlext::{self, ANDROID_DLEXT_USE_LIBRARY_FD, android_dlextinfo};
 use crate::Error;
-use std::ffi::CStr;
+use crate::apk;
+use crate::elf::{DebugSymbols, Dynamic, ElfFile, Image, Segment};
+use crate::module::{ModuleInfo, loaded_modules, module_of_handle};
+use std::ffi::{CStr, CString};
+use std::fs::File;
+use std::io::Write;
 use std::mem::{size_of, transmute_copy};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
 use std::os::raw::c_void;
 use std::ptr::NonNull;
+use std::sync::OnceLock;
 
 type Result<T> = std::result::Result<T, Error>;
 
@@ -17,6 +25,23 @@ obtained from the library become dangling symbols.
 #[derive(Debug)]
 pub struct Library {
     handle: Handle,
+    /// Handle returned by the linker when the library was loaded by this crate.
+    linker: Handle,
+    /// File the library was loaded from, kept open for the lifetime of the handle.
+    fd: Option<OwnedFd>,
+    /// Program headers and dynamic section, read from memory on first use.
+    image: OnceLock<Box<(Image<'static>, Dynamic)>>,
+    /// Debugging symbols, read from the library file on first use.
+    debug_symbols: OnceLock<Option<DebugSymbols>>,
+}
+
+/// Whether [`Library::from_dlopen_handle`] takes over the reference held by a `dlopen` handle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HandleOwnership {
+    /// The handle is closed with `dlclose` when the library is dropped.
+    Owned,
+    /// The handle stays open; the caller remains responsible for closing it.
+    Borrowed,
 }
 
 impl Library {
@@ -26,6 +51,10 @@ impl Library {
         handle
             .map(|handle| Self {
                 handle: handle.as_ptr(),
+                linker: std::ptr::null_mut(),
+                fd: None,
+                image: OnceLock::new(),
+                debug_symbols: OnceLock::new(),
             })
             .ok_or_else(|| {
                 Error::OpeningLibraryError("Cannot create `Library` from null pointer.".to_string())
@@ -52,7 +81,90 @@ impl Library {
         }
     }
 
+    /// Loads a library from an ELF image in memory, without writing it to disk.
+    ///
+    /// The bytes are copied to an anonymous file created with `memfd_create`, which is loaded
+    /// with `android_dlopen_ext` and `ANDROID_DLEXT_USE_LIBRARY_FD` (or with `dlopen` of its
+    /// `/proc/self/fd` path where `android_dlopen_ext` is not available). `name` names the file
+    /// and the module. Dropping the library closes both the linker handle and the file.
+    ///
+    /// # Safety
+    /// Loading the library runs its initializers: `bytes` must be a trusted shared object
+    /// built for the current architecture.
+    pub unsafe fn open_bytes(bytes: &[u8], name: &CStr) -> Result<Self> {
+        let error = |msg: String| {
+            Error::OpeningLibraryError(format!("`{}`: {}", name.to_string_lossy(), msg))
+        };
+        if name.is_empty() {
+            return Err(Error::OpeningLibraryError(
+                "The library name must not be empty.".to_string(),
+            ));
+        }
+        let elf = ElfFile::parse(bytes.to_vec())?;
+
+        let fd = unsafe { libc::syscall(libc::SYS_memfd_create, name.as_ptr(), libc::MFD_CLOEXEC) };
+        if fd < 0 {
+            let err = std::io::Error::last_os_error();
+            return Err(error(format!("memfd_create failed: {}", err)));
+        }
+        let mut file = unsafe { File::from_raw_fd(fd as i32) };
+        file.write_all(bytes)
+            .map_err(|err| error(format!("cannot write the memfd: {}", err)))?;
+        let fd = OwnedFd::from(file);
+
+        let linker = unsafe {
+            match dlext::android_dlopen_ext() {
+                Some(android_dlopen_ext) => {
+                    let info = android_dlextinfo {
+                        flags: ANDROID_DLEXT_USE_LIBRARY_FD,
+                        library_fd: fd.as_raw_fd(),
+                        ..Default::default()
+                    };
+                    android_dlopen_ext(name.as_ptr(), libc::RTLD_NOW, &info)
+                }
+                None => {
+                    let path = CString::new(format!("/proc/self/fd/{}", fd.as_raw_fd())).unwrap();
+                    libc::dlopen(path.as_ptr(), libc::RTLD_NOW)
+                }
+            }
+        };
+        if linker.is_null() {
+            return Err(error(dlext::dlerror()));
+        }
+
+        // Find the module the linker just mapped: the most recent one loaded from the memfd
+        // with the same program headers.
+        let segments = elf.image().segments().to_vec();
+        let memfd = format!("memfd:{}", name.to_string_lossy());
+        let module = loaded_modules().into_iter().rev().find(|module| {
+            let path = module.path().to_string_lossy();
+            let named = path.contains(&memfd) || module.basename() == name;
+            named
+                && module.phdrs().len() == segments.len()
+                && module.phdrs().iter().zip(&segments).all(|(phdr, segment)| {
+                    let phdr = Segment::from_phdr(phdr);
+                    phdr.p_type == segment.p_type
+                        && phdr.p_vaddr == segment.p_vaddr
+                        && phdr.p_memsz == segment.p_memsz
+                })
+        });
+        match module.map(|module| module.open_raw()) {
+            Some(Ok(library)) => Ok(library.owning(linker, Some(fd))),
+            _ => {
+                unsafe { libc::dlclose(linker) };
+                Err(error("the loaded module could not be found".to_string()))
+            }
+        }
+    }
+
     /// Obtains a symbol from the opened library.
+    ///
+    /// For a thread-local variable (`STT_TLS`), the result is an offset in the TLS block of
+    /// the library rather than an address, see
+    /// [`Library::tls_symbol`](crate::Library::tls_symbol).    ///
+    /// Like `xdl_sym`, the address in the symbol table is returned as is: for an indirect
+    /// function (`STT_GNU_IFUNC`) it is the resolver, not the implementation.
+    /// [`crate::Library`] and [`resolve_batch`](Self::resolve_batch) run the resolver.
     pub unsafe fn symbol<T: Sized>(
         &self,
         name: &CStr,
@@ -72,6 +184,8 @@ impl Library {
     }
 
     /// Obtains a debug symbol from the opened library.
+    ///
+    /// As with [`symbol`](Self::symbol), the resolver of an indirect function is returned.
     pub unsafe fn debug_symbol<T: Sized>(
         &self,
         name: &CStr,
@@ -83,13 +197,152 @@ impl Library {
                 Some(size) => size,
                 None => std::ptr::null_mut(),
             };
-            let symbol = NonNull::new(xdl_dsym(self.handle, name.as_ptr(), size_ptr));
+            let symbol = NonNull::new(xdl_dsym(self.handle, name.as_ptr(), size_ptr))
+                .or_else(|| self.apk_debug_symbol(name, size_ptr));
             symbol
                 .map(|symbol| transmute_copy(&symbol.as_ptr()))
                 .ok_or_else(|| Error::SymbolNotFound(name.to_string_lossy().to_string()))
         }
     }
 
+    /// Looks up the debugging symbols of a library loaded from inside an APK, which `xdl_dsym`
+    /// can not open. They are read from the stored entry, including `.gnu_debugdata`, and
+    /// indexed once per library, see [`debug_symbols`](Self::debug_symbols).
+    fn apk_debug_symbol(&self, name: &CStr, size_ptr: *mut usize) -> Option<NonNull<c_void>> {
+        let info = self.info().ok()?;
+        if info.dli_fname.is_null() {
+            return None;
+        }
+        let path = unsafe { CStr::from_ptr(info.dli_fname) }.to_str().ok()?;
+        apk::split_path(path)?;
+        let symbol = self.debug_symbols()?.get(name.to_bytes())?;
+        if !size_ptr.is_null() {
+            unsafe { *size_ptr = symbol.size as usize };
+        }
+        NonNull::new((info.dli_fbase as usize).wrapping_add(symbol.value as usize) as *mut c_void)
+    }
+
+    /// Returns library-level information about the opened library, see [`xdl_info`].
+    ///
+    /// The symbol-related fields of the returned structure are always null.
+    pub fn info(&self) -> Result<xdl_info_t> {
+        unsafe {
+            let mut info: xdl_info_t = std::mem::zeroed();
+            match xdl_info(
+                self.handle,
+                XDL_DI_DLINFO,
+                &mut info as *mut _ as *mut c_void,
+            ) {
+                0 => Ok(info),
+                _ => Err(Error::OpeningLibraryError(
+                    "Cannot get information about the library.".to_string(),
+                )),
+            }
+        }
+    }
+
+    /// Creates a library from a handle returned by `dlopen` or `android_dlopen_ext`.
+    ///
+    /// The module is found by looking up its exported symbols through the handle with
+    /// `dlsym`, which does not load anything nor depend on the namespace of the module, and is
+    /// then opened with `xdl_open2`. With
+    /// [`HandleOwnership::Owned`], the library calls `dlclose` on `handle` when dropped.
+    ///
+    /// Without a name to narrow the search, up to 4 symbols of every loaded module are looked
+    /// up, so this is slow in processes with many modules. A module that exports no symbol
+    /// cannot be found this way: this fails, or opens a dependency of the module whose
+    /// symbols are reachable through the handle.
+    ///
+    /// # Safety
+    /// `handle` must be a live handle returned by the linker. With
+    /// [`HandleOwnership::Owned`], the caller must not close it; with
+    /// [`HandleOwnership::Borrowed`], it must stay open for the lifetime of the library.
+    pub unsafe fn from_dlopen_handle(handle: Handle, ownership: HandleOwnership) -> Result<Self> {
+        if handle.is_null() {
+            return Err(Error::OpeningLibraryError(
+                "Cannot create `Library` from null pointer.".to_string(),
+            ));
+        }
+        let module = module_of_handle(handle, |_| true).ok_or_else(|| {
+            Error::OpeningLibraryError(format!("no loaded module matches the handle {:p}", handle))
+        })?;
+        let library = module.open_raw()?;
+        Ok(match ownership {
+            HandleOwnership::Owned => library.owning(handle, None),
+            HandleOwnership::Borrowed => library,
+        })
+    }
+
+    /// Returns the loaded module of the library, as reported by `xdl_iterate_phdr`.
+    pub fn module(&self) -> Result<ModuleInfo> {
+        let info = self.info()?;
+        loaded_modules()
+            .into_iter()
+            .find(|module| {
+                module.base() == info.dli_fbase as usize
+                    && module.phdr_address() == info.dlpi_phdr as usize
+            })
+            .ok_or_else(|| {
+                Error::OpeningLibraryError("The library is no longer loaded.".to_string())
+            })
+    }
+
+    /// Reads the file the library was loaded from, or its stored entry for a library loaded
+    /// from inside an APK.
+    pub fn elf_file(&self) -> Result<ElfFile> {
+        let info = self.info()?;
+        if info.dli_fname.is_null() {
+            return Err(Error::OpeningLibraryError(
+                "The library has no path.".to_string(),
+            ));
+        }
+        let path = unsafe { CStr::from_ptr(info.dli_fname) }.to_string_lossy();
+        match apk::split_path(&path) {
+            Some((apk, entry)) => ElfFile::open_in_apk(apk, entry),
+            None => ElfFile::open(&*path),
+        }
+    }
+
+    /// Returns the image of the library in memory and its dynamic section, parsed on the
+    /// first successful call.
+    pub(crate) fn image(&self) -> Result<(&Image<'static>, &Dynamic)> {
+        let (image, dynamic) = match self.image.get() {
+            Some(image) => &**image,
+            None => {
+                // The image is dropped together with the library, which keeps it loaded.
+                let image = unsafe { Image::from_library(self)? };
+                let dynamic = image.require_dynamic()?;
+                &**self.image.get_or_init(|| Box::new((image, dynamic)))
+            }
+        };
+        Ok((image, dynamic))
+    }
+
+    /// Returns the debugging symbols of the library file: `.symtab` and the `.symtab` of
+    /// `.gnu_debugdata`, read and indexed on the first call.
+    ///
+    /// Returns `None` if the file can not be read.
+    pub(crate) fn debug_symbols(&self) -> Option<&DebugSymbols> {
+        self.debug_symbols
+            .get_or_init(|| {
+                let file = self.elf_file().ok()?;
+                let debugdata = file
+                    .gnu_debugdata()
+                    .and_then(decompress)
+                    .and_then(|data| ElfFile::parse(data).ok());
+                Some(DebugSymbols::new(&file, debugdata.as_ref()))
+            })
+            .as_ref()
+    }
+
+    /// Makes the library own the handle returned by the linker (and the file it was loaded
+    /// from), so that both are closed on drop.
+    pub(crate) fn owning(mut self, linker: Handle, fd: Option<OwnedFd>) -> Self {
+        self.linker = linker;
+        self.fd = fd;
+        self
+    }
+
     /// Returns the raw handle for the opened library.
     pub unsafe fn handle(&self) -> Handle {
         self.handle
@@ -103,6 +356,9 @@ impl Drop for Library {
             if !handle.is_null() {
                 libc::dlclose(handle);
             }
+            if !self.linker.is_null() {
+                libc::dlclose(self.linker);
+            }
         }
     }
 }
@@ -110,8 +366,24 @@ impl Drop for Library {
 unsafe impl Send for Library {}
 unsafe impl Sync for Library {}
 
+/// Decompresses `.gnu_debugdata` with the xz decoder of xDL.
+fn decompress(data: &[u8]) -> Option<Vec<u8>> {
+    unsafe {
+        let mut dst = std::ptr::null_mut();
+        let mut dst_size = 0;
+        // The decoder does not write to its input.
+        let src = data.as_ptr() as *mut u8;
+        if xdl_lzma_decompress(src, data.len(), &mut dst, &mut dst_size) != 0 || dst.is_null() {
+            return None;
+        }
+        let data = std::slice::from_raw_parts(dst, dst_size).to_vec();
+        libc::free(dst as *mut c_void);
+        Some(data)
+    }
+}
+
 #[inline]
-const fn assert_type_size<T: Sized>() {
+pub(crate) const fn assert_type_size<T: Sized>() {
     const {
         assert!(
             size_of::<T>() == size_of::<*mut ()>(),
@@ -1,4 +1,15 @@
 pub mod api;
+mod batch;
+pub mod dlext;
+mod ifunc;
+mod kind;
 mod library;
+mod options;
+mod prehashed;
 
+pub use batch::{ResolvedSymbol, SymbolRequest};
+pub(crate) use ifunc::call_resolver;
+pub use kind::SymbolKind;
 pub use library::*;
+pub use options::*;
+pub use prehashed::SymbolHash;
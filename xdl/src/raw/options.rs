@@ -0,0 +1,356 @@
+use super::Library;
+use super::api::*;
+use super::dlext::{self, *};
+use crate::Error;
+use crate::module::{ModuleInfo, find_loaded, loaded_modules};
+use std::ffi::{CStr, CString};
+use std::os::fd::RawFd;
+use std::os::raw::{c_char, c_int, c_void};
+
+type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(target_pointer_width = "64")]
+const LINKER: &CStr = c"linker64";
+#[cfg(target_pointer_width = "32")]
+const LINKER: &CStr = c"linker";
+
+/// A linker namespace to load a library into, see [`OpenOptions::namespace`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Namespace {
+    /// The linker's default namespace (`g_default_namespace`), the one of system libraries.
+    Default,
+    /// A namespace exported by the linker configuration, e.g. `sphal`, `vndk` or `rs`.
+    Exported(CString),
+    /// A namespace pointer obtained elsewhere, e.g. from `android_create_namespace`.
+    Raw(*mut android_namespace_t),
+}
+
+impl Namespace {
+    /// Looks up the namespace in the linker.
+    pub fn resolve(&self) -> Result<*mut android_namespace_t> {
+        let error = |msg: String| Error::OpeningLibraryError(format!("namespace: {}", msg));
+        let namespace = match self {
+            Namespace::Raw(namespace) => *namespace,
+            Namespace::Default => unsafe {
+                Library::open(LINKER)?
+                    .debug_symbol::<*mut android_namespace_t>(c"__dl_g_default_namespace", None)
+                    .map_err(|_| error("`g_default_namespace` not found".to_string()))?
+            },
+            Namespace::Exported(name) => unsafe {
+                type GetExportedNamespace =
+                    unsafe extern "C" fn(*const c_char) -> *mut android_namespace_t;
+                let mut function = libc::dlsym(
+                    libc::RTLD_DEFAULT,
+                    c"android_get_exported_namespace".as_ptr(),
+                );
+                if function.is_null() {
+                    function = Library::open(LINKER)?
+                        .symbol::<*mut c_void>(c"__loader_android_get_exported_namespace", None)
+                        .unwrap_or(std::ptr::null_mut());
+                }
+                if function.is_null() {
+                    return Err(error("exported namespaces are not supported".to_string()));
+                }
+                let function = std::mem::transmute::<*mut c_void, GetExportedNamespace>(function);
+                function(name.as_ptr())
+            },
+        };
+        if namespace.is_null() {
+            Err(error(format!("{:?} not found", self)))
+        } else {
+            Ok(namespace)
+        }
+    }
+}
+
+/// Which instance to open when a library is loaded more than once, e.g. a system and an app
+/// copy of `libc++_shared.so`, see [`OpenOptions::duplicates`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum DuplicatePolicy {
+    /// The instance loaded first, like `xdl_open`.
+    #[default]
+    First,
+    /// The first instance loaded from the app (see [`ModuleInfo::is_app`]), otherwise the
+    /// first one.
+    PreferApp,
+    /// The first instance loaded from a system partition (see [`ModuleInfo::is_system`]),
+    /// otherwise the first one.
+    PreferSystem,
+    /// Fail with [`Error::AmbiguousLibrary`] if more than one instance is loaded.
+    ErrorIfAmbiguous,
+}
+
+impl DuplicatePolicy {
+    /// Chooses one of `candidates`, the instances of `name` in load order.
+    fn select(self, name: &CStr, mut candidates: Vec<ModuleInfo>) -> Result<Option<ModuleInfo>> {
+        let position = match self {
+            DuplicatePolicy::First => 0,
+            DuplicatePolicy::PreferApp => {
+                candidates.iter().position(ModuleInfo::is_app).unwrap_or(0)
+            }
+            DuplicatePolicy::PreferSystem => candidates
+                .iter()
+                .position(ModuleInfo::is_system)
+                .unwrap_or(0),
+            DuplicatePolicy::ErrorIfAmbiguous if candidates.len() > 1 => {
+                return Err(Error::AmbiguousLibrary(
+                    name.to_string_lossy().into_owned(),
+                    candidates
+                        .iter()
+                        .map(|module| module.path().to_string_lossy().into_owned())
+                        .collect(),
+                ));
+            }
+            DuplicatePolicy::ErrorIfAmbiguous => 0,
+        };
+        Ok((position < candidates.len()).then(|| candidates.swap_remove(position)))
+    }
+}
+
+/**
+Options for loading a library with `android_dlopen_ext`, see [`Library::open_with`].
+
+`xdl_open` with a force-load flag calls plain `dlopen`, which loads the library into the
+caller's namespace. `OpenOptions` exposes the `android_dlextinfo` extensions instead.
+
+```no_run
+use android_xdl::{Library, Namespace, OpenOptions};
+
+let options = OpenOptions::new()
+    .namespace(Namespace::Exported(c"sphal".into()))
+    .nodelete();
+let library = unsafe { Library::open_with(c"vendor.foo.hal.so", &options) }.unwrap();
+```
+*/
+#[derive(Debug, Clone)]
+pub struct OpenOptions {
+    load: bool,
+    duplicates: DuplicatePolicy,
+    rtld_flags: c_int,
+    namespace: Option<Namespace>,
+    reserved: Option<(*mut c_void, usize, bool)>,
+    library_fd: Option<(RawFd, Option<i64>)>,
+    force_load: bool,
+}
+
+impl Default for OpenOptions {
+    fn default() -> Self {
+        Self {
+            load: true,
+            duplicates: DuplicatePolicy::First,
+            rtld_flags: libc::RTLD_NOW | libc::RTLD_LOCAL,
+            namespace: None,
+            reserved: None,
+            library_fd: None,
+            force_load: false,
+        }
+    }
+}
+
+impl OpenOptions {
+    /// Creates options loading with `RTLD_NOW | RTLD_LOCAL` into the caller's namespace.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only opens an instance that is already loaded, without calling the linker.
+    pub fn no_load(mut self) -> Self {
+        self.load = false;
+        self
+    }
+
+    /// Chooses between several loaded instances of the library, see [`DuplicatePolicy`].
+    ///
+    /// Only applies with [`no_load`](Self::no_load): the linker picks the instance itself when
+    /// loading, so [`Library::open_with`] fails if any other policy than
+    /// [`DuplicatePolicy::First`] is combined with a loading open.
+    pub fn duplicates(mut self, policy: DuplicatePolicy) -> Self {
+        self.duplicates = policy;
+        self
+    }
+
+    /// Replaces the `RTLD_*` flags passed to the linker.
+    pub fn rtld_flags(mut self, flags: c_int) -> Self {
+        self.rtld_flags = flags;
+        self
+    }
+
+    /// Resolves all symbols when the library is loaded (`RTLD_NOW`, the default).
+    pub fn now(mut self) -> Self {
+        self.rtld_flags = (self.rtld_flags & !libc::RTLD_LAZY) | libc::RTLD_NOW;
+        self
+    }
+
+    /// Makes the symbols of the library available to libraries loaded later (`RTLD_GLOBAL`).
+    pub fn global(mut self) -> Self {
+        self.rtld_flags = (self.rtld_flags & !libc::RTLD_LOCAL) | libc::RTLD_GLOBAL;
+        self
+    }
+
+    /// Keeps the symbols of the library to itself (`RTLD_LOCAL`, the default).
+    pub fn local(mut self) -> Self {
+        self.rtld_flags = (self.rtld_flags & !libc::RTLD_GLOBAL) | libc::RTLD_LOCAL;
+        self
+    }
+
+    /// Never unloads the library, even after the last handle is closed (`RTLD_NODELETE`).
+    pub fn nodelete(mut self) -> Self {
+        self.rtld_flags |= libc::RTLD_NODELETE;
+        self
+    }
+
+    /// Loads the library into a specific linker namespace (`ANDROID_DLEXT_USE_NAMESPACE`).
+    pub fn namespace(mut self, namespace: Namespace) -> Self {
+        self.namespace = Some(namespace);
+        self
+    }
+
+    /// Loads the library into the reserved region `[address, address + size)`
+    /// (`ANDROID_DLEXT_RESERVED_ADDRESS`). Loading fails if it does not fit.
+    pub fn reserved_address(mut self, address: *mut c_void, size: usize) -> Self {
+        self.reserved = Some((address, size, false));
+        self
+    }
+
+    /// Like [`reserved_address`](Self::reserved_address), but falls back to any address if the
+    /// library does not fit (`ANDROID_DLEXT_RESERVED_ADDRESS_HINT`).
+    pub fn reserved_address_hint(mut self, address: *mut c_void, size: usize) -> Self {
+        self.reserved = Some((address, size, true));
+        self
+    }
+
+    /// Reads the library from `fd` instead of opening it by name, starting at `offset` if
+    /// given (`ANDROID_DLEXT_USE_LIBRARY_FD[_OFFSET]`). The file descriptor is not closed.
+    pub fn library_fd(mut self, fd: RawFd, offset: Option<i64>) -> Self {
+        self.library_fd = Some((fd, offset));
+        self
+    }
+
+    /// Loads the library even if one with the same name is already loaded
+    /// (`ANDROID_DLEXT_FORCE_LOAD`).
+    pub fn force_load(mut self, force_load: bool) -> Self {
+        self.force_load = force_load;
+        self
+    }
+
+    /// Builds the `android_dlextinfo`, resolving the namespace.
+    fn extinfo(&self) -> Result<android_dlextinfo> {
+        let mut info = android_dlextinfo::default();
+        if let Some(namespace) = &self.namespace {
+            info.flags |= ANDROID_DLEXT_USE_NAMESPACE;
+            info.library_namespace = namespace.resolve()?;
+        }
+        if let Some((address, size, hint)) = self.reserved {
+            info.flags |= if hint {
+                ANDROID_DLEXT_RESERVED_ADDRESS_HINT
+            } else {
+                ANDROID_DLEXT_RESERVED_ADDRESS
+            };
+            info.reserved_addr = address;
+            info.reserved_size = size;
+        }
+        if let Some((fd, offset)) = self.library_fd {
+            info.flags |= ANDROID_DLEXT_USE_LIBRARY_FD;
+            info.library_fd = fd;
+            if let Some(offset) = offset {
+                info.flags |= ANDROID_DLEXT_USE_LIBRARY_FD_OFFSET;
+                info.library_fd_offset = offset;
+            }
+        }
+        if self.force_load {
+            info.flags |= ANDROID_DLEXT_FORCE_LOAD;
+        }
+        Ok(info)
+    }
+}
+
+impl Library {
+    /// Loads a library with `android_dlopen_ext` and the given options, then opens it with xDL.
+    ///
+    /// The linker handle is owned by the returned library and closed on drop. With
+    /// [`OpenOptions::no_load`], an already loaded instance is opened instead, chosen by the
+    /// [`DuplicatePolicy`] if there are several.
+    ///
+    /// Otherwise, the module is identified from the handle the linker returned, by looking up
+    /// its exported symbols through the handle. Loading with a [`DuplicatePolicy`] other than
+    /// [`DuplicatePolicy::First`] is an error, as the linker decides which instance to return.
+    ///
+    /// # Safety
+    /// Loading the library runs its initializers, and a raw namespace or reserved region must
+    /// be valid.
+    pub unsafe fn open_with(name: &CStr, options: &OpenOptions) -> Result<Self> {
+        let error = |msg: String| {
+            Error::OpeningLibraryError(format!("`{}`: {}", name.to_string_lossy(), msg))
+        };
+        if name.is_empty() {
+            return Err(Error::OpeningLibraryError(
+                "The library name must not be empty.".to_string(),
+            ));
+        }
+        if !options.load {
+            let candidates = candidates(&loaded_modules(), name, None);
+            return match options.duplicates.select(name, candidates)? {
+                Some(module) => module.open_raw(),
+                None => Err(error("not loaded".to_string())),
+            };
+        }
+        if options.duplicates != DuplicatePolicy::First {
+            return Err(error(format!(
+                "the duplicate policy {:?} requires `no_load`",
+                options.duplicates
+            )));
+        }
+        let info = options.extinfo()?;
+        let linker = unsafe {
+            match dlext::android_dlopen_ext() {
+                Some(android_dlopen_ext) => {
+                    android_dlopen_ext(name.as_ptr(), options.rtld_flags, &info)
+                }
+                None if info.flags == 0 => libc::dlopen(name.as_ptr(), options.rtld_flags),
+                None => return Err(error("android_dlopen_ext is not available".to_string())),
+            }
+        };
+        if linker.is_null() {
+            return Err(error(dlext::dlerror()));
+        }
+        let fd_path = options.library_fd.and_then(|(fd, _)| {
+            std::fs::read_link(format!("/proc/self/fd/{}", fd))
+                .ok()
+                .map(|path| path.to_string_lossy().into_owned())
+        });
+        let module = find_loaded(linker, name, fd_path.as_deref());
+        match module.map(|module| module.open_raw()) {
+            Some(Ok(library)) => Ok(library.owning(linker, None)),
+            result => {
+                unsafe { libc::dlclose(linker) };
+                match result {
+                    Some(Err(err)) => Err(err),
+                    _ => Err(error("the loaded module could not be found".to_string())),
+                }
+            }
+        }
+    }
+
+    /// Opens every loaded instance of `name` (a basename or full path), in load order.
+    pub fn open_all(name: &CStr) -> Vec<Self> {
+        candidates(&loaded_modules(), name, None)
+            .iter()
+            .filter_map(|module| module.open_raw().ok())
+            .collect()
+    }
+}
+
+/// The loaded modules matching `name` (a full path if it contains `/`, otherwise a basename)
+/// or loaded from `fd_path`.
+fn candidates(modules: &[ModuleInfo], name: &CStr, fd_path: Option<&str>) -> Vec<ModuleInfo> {
+    let name = name.to_string_lossy();
+    modules
+        .iter()
+        .filter(|module| {
+            module.matches(&name)
+                || fd_path
+                    .is_some_and(|fd_path| module.path().to_string_lossy().starts_with(fd_path))
+        })
+        .cloned()
+        .collect()
+}
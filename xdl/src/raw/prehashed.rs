@@ -0,0 +1,65 @@
+use super::{Library, assert_type_size};
+use crate::Error;
+use crate::elf::{gnu_hash, sysv_hash};
+use std::ffi::CStr;
+use std::mem::transmute_copy;
+use std::os::raw::c_void;
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// The `DT_GNU_HASH` and `DT_HASH` hashes of a symbol name, see
+/// [`Library::symbol_prehashed`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SymbolHash {
+    pub gnu: u32,
+    pub sysv: u32,
+}
+
+impl SymbolHash {
+    /// Hashes `name`, at compile time in a `const` context.
+    pub const fn of(name: &CStr) -> Self {
+        Self {
+            gnu: gnu_hash(name.to_bytes()),
+            sysv: sysv_hash(name.to_bytes()),
+        }
+    }
+}
+
+impl Library {
+    /// Like [`symbol`](Self::symbol), but walks `DT_GNU_HASH` (or `DT_HASH`) with hashes
+    /// computed in advance, e.g. by `#[derive(NativeBridge)]` or [`SymbolHash::of`] in a
+    /// `const`.
+    ///
+    /// The program headers and dynamic section of the library are parsed on the first call
+    /// and reused. Falls back to `xdl_sym` if the hash tables of the library can not be read.
+    /// As with [`symbol`](Self::symbol), the resolver of an indirect function is returned.
+    ///
+    /// # Safety
+    /// `hash` must be the hash of `name`, and `T` must match the type of the symbol, as with
+    /// [`symbol`](Self::symbol).
+    pub unsafe fn symbol_prehashed<T>(
+        &self,
+        name: &CStr,
+        hash: SymbolHash,
+        symbol_size: Option<&mut usize>,
+    ) -> Result<T> {
+        assert_type_size::<T>();
+        let Ok((image, dynamic)) = self.image() else {
+            return unsafe { self.symbol(name, symbol_size) };
+        };
+        let sym = image
+            .lookup_dynsym_hashed(dynamic, name, hash.gnu, hash.sysv)
+            .ok_or_else(|| Error::SymbolNotFound(name.to_string_lossy().to_string()))?;
+        let address = image
+            .bias()
+            .unwrap_or_default()
+            .wrapping_add(sym.st_value as usize);
+        if address == 0 {
+            return Err(Error::SymbolNotFound(name.to_string_lossy().to_string()));
+        }
+        if let Some(size) = symbol_size {
+            *size = sym.st_size as usize;
+        }
+        Ok(unsafe { transmute_copy(&(address as *mut c_void)) })
+    }
+}
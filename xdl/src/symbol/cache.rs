@@ -0,0 +1,130 @@
+use crate::Error;
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_void;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{PoisonError, RwLock, RwLockReadGuard};
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Address and size by name, of `.dynsym` symbols (`[0]`) and debug symbols (`[1]`).
+///
+/// Keyed by `CString` so that a lookup with a `&CStr` does not allocate.
+type Symbols = [HashMap<CString, (usize, usize)>; 2];
+
+/// Hit and miss counters of a [`SymbolCache`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Lookups answered from the cache.
+    pub hits: u64,
+    /// Lookups not answered from the cache, which then searched the symbol tables, whether the
+    /// symbol was found or not.
+    pub misses: u64,
+    /// Number of cached symbols.
+    pub entries: usize,
+}
+
+/// A symbol stored in a [`SymbolCache`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedSymbol {
+    pub name: CString,
+    /// `true` if the symbol was looked up in `.symtab` with `xdl_dsym`.
+    pub debug: bool,
+    pub address: usize,
+    pub size: usize,
+}
+
+/// A thread-safe cache of symbol lookups, from name to address and size, see
+/// [`Library::with_cache`](super::Library::with_cache).
+///
+/// Symbols that are not found are not cached.
+#[derive(Debug, Default)]
+pub struct SymbolCache {
+    symbols: RwLock<Symbols>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl SymbolCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the cached address and size of `name`, or looks it up with `resolve`.
+    pub(crate) fn get_or_resolve<F>(
+        &self,
+        name: &CStr,
+        debug: bool,
+        resolve: F,
+    ) -> Result<(*mut c_void, usize)>
+    where
+        F: FnOnce() -> Result<(*mut c_void, usize)>,
+    {
+        if let Some((address, size)) = self.get(name, debug) {
+            return Ok((address as *mut c_void, size));
+        }
+        let (address, size) = resolve()?;
+        self.insert(name, debug, address as usize, size);
+        Ok((address, size))
+    }
+
+    /// Returns the cached address and size of `name`, counting a hit or a miss.
+    pub(crate) fn get(&self, name: &CStr, debug: bool) -> Option<(usize, usize)> {
+        match self.read()[debug as usize].get(name) {
+            Some(&symbol) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(symbol)
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    pub(crate) fn insert(&self, name: &CStr, debug: bool, address: usize, size: usize) {
+        let mut symbols = self.symbols.write().unwrap_or_else(PoisonError::into_inner);
+        symbols[debug as usize].insert(name.to_owned(), (address, size));
+    }
+
+    /// Returns the hit and miss counters and the number of cached symbols.
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.read().iter().map(HashMap::len).sum(),
+        }
+    }
+
+    /// Returns a snapshot of the cached symbols, sorted by name.
+    pub fn entries(&self) -> Vec<CachedSymbol> {
+        let symbols = self.read();
+        let mut entries = [false, true]
+            .into_iter()
+            .flat_map(|debug| {
+                symbols[debug as usize]
+                    .iter()
+                    .map(move |(name, &(address, size))| CachedSymbol {
+                        name: name.clone(),
+                        debug,
+                        address,
+                        size,
+                    })
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| (&a.name, a.debug).cmp(&(&b.name, b.debug)));
+        entries
+    }
+
+    /// Removes all cached symbols and resets the counters.
+    pub fn clear(&self) {
+        let mut symbols = self.symbols.write().unwrap_or_else(PoisonError::into_inner);
+        symbols.iter_mut().for_each(HashMap::clear);
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Symbols> {
+        self.symbols.read().unwrap_or_else(PoisonError::into_inner)
+    }
+}
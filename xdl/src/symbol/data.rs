@@ -0,0 +1,92 @@
+use super::{Library, Symbol};
+use crate::Error;
+use crate::elf::Image;
+use std::ffi::CStr;
+use std::mem::{align_of, size_of};
+
+type Result<T> = std::result::Result<T, Error>;
+
+impl Library {
+    /// Obtains an array data symbol as a slice, using the size of its `.dynsym` entry.
+    ///
+    /// Fails if the size is not a multiple of `size_of::<T>()`, if the symbol is not aligned
+    /// for `T`, or if the data does not lie within a loaded segment of the library.
+    pub fn symbol_slice<T>(&self, name: &CStr) -> Result<Symbol<'_, &[T]>> {
+        let error =
+            |msg: String| Error::InvalidSymbol(format!("`{}`: {}", name.to_string_lossy(), msg));
+        let (symbol, size) = self.symbol_and_size::<*const u8>(name)?;
+        let address = *symbol as usize;
+        let element = size_of::<T>();
+        if element == 0 || !size.is_multiple_of(element) {
+            return Err(error(format!(
+                "size {} is not a multiple of the element size {}",
+                size, element
+            )));
+        }
+        if !address.is_multiple_of(align_of::<T>()) {
+            return Err(error(format!(
+                "{:#x} is not aligned to {} bytes",
+                address,
+                align_of::<T>()
+            )));
+        }
+        let image = unsafe { Image::from_library(self)? };
+        let bytes = image
+            .bytes(vaddr(&image, address), size as u64)
+            .ok_or_else(|| error("the data is not within a loaded segment".to_string()))?;
+        let slice =
+            unsafe { std::slice::from_raw_parts(bytes.as_ptr() as *const T, size / element) };
+        Ok(Symbol::new(slice))
+    }
+
+    /// Obtains a string stored in a data symbol, a `const char[]`.
+    ///
+    /// The string must be NUL-terminated within the size of the symbol, or within its loaded
+    /// segment if the size is not recorded. Use [`symbol_cstr_ptr`](Self::symbol_cstr_ptr) for
+    /// a `const char *`.
+    pub fn symbol_cstr_array(&self, name: &CStr) -> Result<Symbol<'_, &CStr>> {
+        let error =
+            |msg: &str| Error::InvalidSymbol(format!("`{}`: {}", name.to_string_lossy(), msg));
+        let (symbol, size) = self.symbol_and_size::<*const u8>(name)?;
+        let image = unsafe { Image::from_library(self)? };
+        let symbol_vaddr = vaddr(&image, *symbol as usize);
+        let string = if size == 0 {
+            image.read_cstr(symbol_vaddr)
+        } else {
+            image
+                .bytes(symbol_vaddr, size as u64)
+                .and_then(|bytes| CStr::from_bytes_until_nul(bytes).ok())
+        };
+        string
+            .map(Symbol::new)
+            .ok_or_else(|| error("no NUL-terminated string within a loaded segment"))
+    }
+
+    /// Obtains the string a pointer data symbol points to, a `const char *`.
+    ///
+    /// The symbol must be a pointer, and the string it currently points to must lie within a
+    /// loaded segment of the library. Use [`symbol_cstr_array`](Self::symbol_cstr_array) for
+    /// a `const char[]`.
+    pub fn symbol_cstr_ptr(&self, name: &CStr) -> Result<Symbol<'_, &CStr>> {
+        let error =
+            |msg: &str| Error::InvalidSymbol(format!("`{}`: {}", name.to_string_lossy(), msg));
+        let (symbol, size) = self.symbol_and_size::<*const u8>(name)?;
+        let address = *symbol as usize;
+        if size != size_of::<usize>() || !address.is_multiple_of(align_of::<usize>()) {
+            return Err(error("the symbol is not a pointer"));
+        }
+        let image = unsafe { Image::from_library(self)? };
+        let pointer = image
+            .read_word(vaddr(&image, address))
+            .ok_or_else(|| error("the pointer is not within a loaded segment"))?;
+        image
+            .read_cstr(vaddr(&image, pointer as usize))
+            .map(Symbol::new)
+            .ok_or_else(|| error("the pointer does not point to a string within the library"))
+    }
+}
+
+/// Converts an address in the process to a virtual address of the mapped `image`.
+pub(super) fn vaddr(image: &Image, address: usize) -> u64 {
+    address.wrapping_sub(image.bias().unwrap_or_default()) as u64
+}
@@ -0,0 +1,180 @@
+use super::Library;
+use super::data::vaddr;
+use crate::Error;
+use crate::elf::Image;
+use std::ffi::CStr;
+use std::marker::PhantomData;
+use std::mem::{align_of, size_of};
+use std::ptr::NonNull;
+use std::sync::atomic::*;
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// A global variable of a library, which other threads may access concurrently.
+///
+/// Unlike `Symbol<&mut T>`, it never hands out a plain reference: the value is accessed with
+/// volatile reads and writes, or atomically through [`atomic`](Self::atomic) when `T` is a
+/// primitive with an atomic counterpart.
+#[derive(Debug, Clone, Copy)]
+pub struct GlobalVar<'lib, T> {
+    ptr: NonNull<T>,
+    _phantom: PhantomData<&'lib T>,
+}
+
+impl<'lib, T> GlobalVar<'lib, T> {
+    /// Returns the address of the variable.
+    pub fn as_ptr(&self) -> *mut T {
+        self.ptr.as_ptr()
+    }
+
+    /// Reads the value with [`read_volatile`](std::ptr::read_volatile).
+    ///
+    /// # Safety
+    /// A volatile read is not synchronized: no other thread may write the variable
+    /// concurrently. Use [`atomic`](Self::atomic) otherwise.
+    pub unsafe fn read_volatile(&self) -> T
+    where
+        T: Copy,
+    {
+        unsafe { self.ptr.as_ptr().read_volatile() }
+    }
+
+    /// Writes the value with [`write_volatile`](std::ptr::write_volatile).
+    ///
+    /// # Safety
+    /// No other thread may access the variable concurrently.
+    pub unsafe fn write_volatile(&self, value: T)
+    where
+        T: Copy,
+    {
+        unsafe { self.ptr.as_ptr().write_volatile(value) }
+    }
+}
+
+impl<'lib, T: AtomicPrimitive> GlobalVar<'lib, T> {
+    /// Views the variable as its atomic counterpart, e.g. `AtomicU32` for `u32`.
+    ///
+    /// # Panics
+    /// If the variable is not aligned for the atomic type, which can only happen for 64-bit
+    /// values on 32-bit x86.
+    pub fn atomic(&self) -> &'lib T::Atomic {
+        let address = self.ptr.as_ptr() as usize;
+        assert!(
+            address.is_multiple_of(align_of::<T::Atomic>()),
+            "{:#x} is not aligned for an atomic access",
+            address
+        );
+        unsafe { &*(self.ptr.as_ptr() as *const T::Atomic) }
+    }
+
+    /// Loads the value atomically.
+    pub fn load(&self, order: Ordering) -> T {
+        T::load(self.atomic(), order)
+    }
+
+    /// Stores the value atomically.
+    pub fn store(&self, value: T, order: Ordering) {
+        T::store(self.atomic(), value, order)
+    }
+}
+
+unsafe impl<'lib, T: Send> Send for GlobalVar<'lib, T> {}
+unsafe impl<'lib, T: Sync> Sync for GlobalVar<'lib, T> {}
+
+/// A primitive type with an atomic counterpart of the same size, see [`GlobalVar::atomic`].
+pub trait AtomicPrimitive: Copy + private::Sealed {
+    /// The atomic type, e.g. `AtomicU32` for `u32`.
+    type Atomic;
+
+    fn load(atomic: &Self::Atomic, order: Ordering) -> Self;
+
+    fn store(atomic: &Self::Atomic, value: Self, order: Ordering);
+}
+
+mod private {
+    pub trait Sealed {}
+}
+
+macro_rules! atomic_primitive {
+    ($($ty:ty => $atomic:ty),* $(,)?) => {$(
+        impl private::Sealed for $ty {}
+
+        impl AtomicPrimitive for $ty {
+            type Atomic = $atomic;
+
+            fn load(atomic: &Self::Atomic, order: Ordering) -> Self {
+                atomic.load(order)
+            }
+
+            fn store(atomic: &Self::Atomic, value: Self, order: Ordering) {
+                atomic.store(value, order)
+            }
+        }
+    )*};
+}
+
+atomic_primitive! {
+    bool => AtomicBool,
+    i8 => AtomicI8,
+    u8 => AtomicU8,
+    i16 => AtomicI16,
+    u16 => AtomicU16,
+    i32 => AtomicI32,
+    u32 => AtomicU32,
+    i64 => AtomicI64,
+    u64 => AtomicU64,
+    isize => AtomicIsize,
+    usize => AtomicUsize,
+}
+
+impl<T> private::Sealed for *mut T {}
+
+impl<T> AtomicPrimitive for *mut T {
+    type Atomic = AtomicPtr<T>;
+
+    fn load(atomic: &Self::Atomic, order: Ordering) -> Self {
+        atomic.load(order)
+    }
+
+    fn store(atomic: &Self::Atomic, value: Self, order: Ordering) {
+        atomic.store(value, order)
+    }
+}
+
+impl Library {
+    /// Obtains a global variable, see [`GlobalVar`].
+    ///
+    /// Fails if the `.dynsym` size of the symbol is smaller than `T`, if the symbol is not
+    /// aligned for `T`, or if it does not lie within a writable segment of the library (e.g.
+    /// in `.rodata` or a RELRO section), since [`GlobalVar`] allows writes.
+    pub fn global_var<T>(&self, name: &CStr) -> Result<GlobalVar<'_, T>> {
+        let error =
+            |msg: String| Error::InvalidSymbol(format!("`{}`: {}", name.to_string_lossy(), msg));
+        let (symbol, size) = self.symbol_and_size::<*mut u8>(name)?;
+        let address = *symbol as usize;
+        if size != 0 && size < size_of::<T>() {
+            return Err(error(format!(
+                "size {} is smaller than {} bytes",
+                size,
+                size_of::<T>()
+            )));
+        }
+        if !address.is_multiple_of(align_of::<T>()) {
+            return Err(error(format!(
+                "{:#x} is not aligned to {} bytes",
+                address,
+                align_of::<T>()
+            )));
+        }
+        let image = unsafe { Image::from_library(self)? };
+        if !image.is_writable(vaddr(&image, address), size_of::<T>() as u64) {
+            return Err(error(
+                "the variable is not within a writable segment".to_string(),
+            ));
+        }
+        Ok(GlobalVar {
+            ptr: unsafe { NonNull::new_unchecked(address as *mut T) },
+            _phantom: PhantomData,
+        })
+    }
+}
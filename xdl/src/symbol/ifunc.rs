@@ -0,0 +1,96 @@
+use super::{Library, Symbol};
+use crate::Error;
+use crate::raw::Library as RowLibrary;
+use crate::raw::{assert_type_size, call_resolver};
+use std::ffi::CStr;
+use std::marker::PhantomData;
+use std::os::raw::c_void;
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// A symbol that may be a GNU indirect function (`STT_GNU_IFUNC`), see
+/// [`Library::symbol_resolved`].
+#[derive(Debug, Clone, Copy)]
+pub enum Resolved<'lib, T> {
+    /// A regular symbol, which can be used as is.
+    Symbol(Symbol<'lib, T>),
+    /// An indirect function: the address is the resolver, which returns the implementation
+    /// selected for the CPU.
+    IFunc(IFunc<'lib, T>),
+}
+
+impl<'lib, T> Resolved<'lib, T> {
+    /// Returns the symbol, running the resolver of an indirect function.
+    pub fn resolve(self) -> Result<Symbol<'lib, T>> {
+        match self {
+            Resolved::Symbol(symbol) => Ok(symbol),
+            Resolved::IFunc(ifunc) => ifunc.resolve(),
+        }
+    }
+
+    /// Returns `true` for an indirect function.
+    pub fn is_ifunc(&self) -> bool {
+        matches!(self, Resolved::IFunc(_))
+    }
+}
+
+/// The resolver of a GNU indirect function.
+#[derive(Debug, Clone, Copy)]
+pub struct IFunc<'lib, T> {
+    resolver: *const c_void,
+    _phantom: PhantomData<&'lib T>,
+}
+
+impl<'lib, T> IFunc<'lib, T> {
+    /// Address of the resolver.
+    pub fn resolver(&self) -> *const c_void {
+        self.resolver
+    }
+
+    /// Runs the resolver with the arguments the linker passes (`AT_HWCAP` and `AT_HWCAP2` on
+    /// ARM) and returns the implementation it selects.
+    pub fn resolve(&self) -> Result<Symbol<'lib, T>> {
+        assert_type_size::<T>();
+        let address = unsafe { call_resolver(self.resolver) };
+        Symbol::from_row(address).ok_or_else(|| {
+            Error::InvalidSymbol(format!("the resolver at {:p} returned null", self.resolver))
+        })
+    }
+}
+
+unsafe impl<'lib, T: Send> Send for IFunc<'lib, T> {}
+unsafe impl<'lib, T: Sync> Sync for IFunc<'lib, T> {}
+
+impl Library {
+    /// Like [`symbol`](Self::symbol), but leaves it to the caller to run the resolver of an
+    /// indirect function (`STT_GNU_IFUNC`), for which `xdl_sym` returns the address of the
+    /// resolver rather than of the function.
+    ///
+    /// Use [`Resolved::resolve`] to run the resolver.
+    pub fn symbol_resolved<T>(&self, name: &CStr) -> Result<Resolved<'_, T>> {
+        let address = unsafe { RowLibrary::symbol::<*const c_void>(self, name, None)? };
+        Ok(resolved(address, self.is_ifunc(name, false)))
+    }
+
+    /// Like [`debug_symbol`](Self::debug_symbol), but leaves it to the caller to run the
+    /// resolver of an indirect function (`STT_GNU_IFUNC`).
+    ///
+    /// The type of the symbol is read from the file the library was loaded from, which is
+    /// indexed once per library.
+    pub fn debug_symbol_resolved<T>(&self, name: &CStr) -> Result<Resolved<'_, T>> {
+        let address = unsafe { RowLibrary::debug_symbol::<*const c_void>(self, name, None)? };
+        Ok(resolved(address, self.is_ifunc(name, true)))
+    }
+}
+
+fn resolved<'lib, T>(address: *const c_void, ifunc: bool) -> Resolved<'lib, T> {
+    assert_type_size::<T>();
+    if ifunc {
+        Resolved::IFunc(IFunc {
+            resolver: address,
+            _phantom: PhantomData,
+        })
+    } else {
+        Resolved::Symbol(unsafe { Symbol::new(std::mem::transmute_copy(&address)) })
+    }
+}
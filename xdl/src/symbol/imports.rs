@@ -0,0 +1,148 @@
+use super::Library;
+use crate::Error;
+use crate::elf::{Image, RelocationKind};
+use crate::raw::api::*;
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_void;
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// A symbol imported by a library, see [`Library::imports`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Import {
+    /// Name of the undefined symbol, as written in `.dynsym`.
+    pub name: String,
+    /// Kind of the relocation filling the slot (`GlobDat`, `JumpSlot` or `Absolute`).
+    pub kind: RelocationKind,
+    /// Address of the GOT (or data) slot holding the resolved address.
+    pub slot: usize,
+    /// Current value of the slot.
+    pub value: usize,
+    /// The loaded module and symbol [`value`](Self::value) points into, `None` if the slot is
+    /// null (an unresolved weak import) or does not point into any loaded module.
+    pub target: Option<ImportTarget>,
+}
+
+impl Import {
+    /// Returns `true` if the slot points to a symbol with the imported name.
+    ///
+    /// A slot pointing elsewhere has most likely been hooked.
+    pub fn is_bound_to_name(&self) -> bool {
+        let name = self.name.split('@').next().unwrap_or_default();
+        self.target
+            .as_ref()
+            .and_then(|target| target.symbol.as_deref())
+            .is_some_and(|symbol| symbol.to_bytes() == name.as_bytes())
+    }
+}
+
+/// Where the value of an [`Import`] slot points to, as reported by [`xdl_addr`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportTarget {
+    /// Pathname of the module containing the address.
+    pub module: CString,
+    /// Load bias of that module.
+    pub module_base: usize,
+    /// Name of the nearest symbol at or below the address, if any.
+    pub symbol: Option<CString>,
+    /// Address of [`symbol`](Self::symbol).
+    pub symbol_address: usize,
+}
+
+impl Library {
+    /// Lists the undefined dynamic symbols referenced by the relocations of the library.
+    ///
+    /// Each entry carries the address of its slot, the value currently stored there and the
+    /// module/symbol that value resolves to. Thread-local imports are not listed, their slots do
+    /// not hold addresses.
+    pub fn imports(&self) -> Result<Vec<Import>> {
+        let image = unsafe { Image::from_library(self)? };
+        let bias = image.bias().unwrap_or_default();
+        let dynamic = image.require_dynamic()?;
+        let mut resolver = AddrResolver::default();
+        let mut imports = Vec::new();
+        for reloc in image.raw_relocations(&dynamic)? {
+            let kind = RelocationKind::classify(image.machine(), reloc.r_type);
+            if reloc.sym == 0
+                || !matches!(
+                    kind,
+                    RelocationKind::GlobDat | RelocationKind::JumpSlot | RelocationKind::Absolute
+                )
+            {
+                continue;
+            }
+            let Some(sym) = image.dynsym(&dynamic, reloc.sym as u64) else {
+                continue;
+            };
+            if !sym.is_undefined() {
+                continue;
+            }
+            let Some(bytes) = image.bytes(reloc.offset, size_of::<usize>() as u64) else {
+                continue;
+            };
+            let value = usize::from_ne_bytes(bytes.try_into().unwrap());
+            imports.push(Import {
+                name: image
+                    .dynstr(&dynamic, sym.st_name as u64)
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                kind,
+                slot: bias.wrapping_add(reloc.offset as usize),
+                value,
+                target: resolver.resolve(value),
+            });
+        }
+        Ok(imports)
+    }
+}
+
+/// Memoizing wrapper around [`xdl_addr`], reusing xDL's handle cache between lookups.
+struct AddrResolver {
+    cache: *mut c_void,
+    resolved: HashMap<usize, Option<ImportTarget>>,
+}
+
+impl Default for AddrResolver {
+    fn default() -> Self {
+        Self {
+            cache: std::ptr::null_mut(),
+            resolved: HashMap::new(),
+        }
+    }
+}
+
+impl AddrResolver {
+    fn resolve(&mut self, addr: usize) -> Option<ImportTarget> {
+        if addr == 0 {
+            return None;
+        }
+        let cache = &mut self.cache;
+        self.resolved
+            .entry(addr)
+            .or_insert_with(|| unsafe {
+                let mut info: xdl_info_t = std::mem::zeroed();
+                if xdl_addr(addr as *mut c_void, &mut info, cache) == 0 || info.dli_fname.is_null()
+                {
+                    return None;
+                }
+                // The strings may live in the cache, copy them before it is cleaned.
+                Some(ImportTarget {
+                    module: CStr::from_ptr(info.dli_fname).to_owned(),
+                    module_base: info.dli_fbase as usize,
+                    symbol: (!info.dli_sname.is_null())
+                        .then(|| CStr::from_ptr(info.dli_sname).to_owned()),
+                    symbol_address: info.dli_saddr as usize,
+                })
+            })
+            .clone()
+    }
+}
+
+impl Drop for AddrResolver {
+    fn drop(&mut self) {
+        if !self.cache.is_null() {
+            unsafe { xdl_addr_clean(&mut self.cache) };
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use super::{Library, Symbol};
+use crate::Error;
+use crate::raw::api::{Handle, XDL_DEFAULT};
+use crate::raw::assert_type_size;
+use crate::raw::{Library as RowLibrary, SymbolRequest};
+use std::ffi::CStr;
+use std::marker::PhantomData;
+use std::mem::{ManuallyDrop, transmute_copy};
+use std::os::raw::c_void;
+use std::sync::OnceLock;
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// A library that is opened on first use, e.g. in a `static`.
+///
+/// If opening the library fails, the error is returned and the next call tries again.
+///
+/// ```no_run
+/// use android_xdl::{LazyLibrary, LazySymbol};
+/// use std::os::raw::{c_char, c_int};
+///
+/// static LIBC: LazyLibrary = LazyLibrary::new(c"libc.so");
+/// static PUTS: LazySymbol<unsafe extern "C" fn(*const c_char) -> c_int> =
+///     LazySymbol::new(&LIBC, c"puts");
+///
+/// fn main() -> Result<(), android_xdl::Error> {
+///     let puts = PUTS.get()?;
+///     unsafe { puts(c"Hello".as_ptr()) };
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct LazyLibrary {
+    name: &'static CStr,
+    flags: i32,
+    library: OnceLock<Library>,
+}
+
+impl LazyLibrary {
+    /// Opens `name` on first use, like [`Library::open`].
+    pub const fn new(name: &'static CStr) -> Self {
+        Self::with_flags(name, XDL_DEFAULT)
+    }
+
+    /// Opens `name` on first use, like [`Library::open_with_flags`].
+    pub const fn with_flags(name: &'static CStr, flags: i32) -> Self {
+        Self {
+            name,
+            flags,
+            library: OnceLock::new(),
+        }
+    }
+
+    pub fn name(&self) -> &'static CStr {
+        self.name
+    }
+
+    /// Returns the library, opening it if this is the first call.
+    pub fn get(&self) -> Result<&Library> {
+        if let Some(library) = self.library.get() {
+            return Ok(library);
+        }
+        let library = Library::open_with_flags(self.name, self.flags)?;
+        // If another thread opened it first, the extra reference is closed here.
+        Ok(self.library.get_or_init(|| library))
+    }
+
+    /// Returns `true` if the library has been opened.
+    pub fn is_loaded(&self) -> bool {
+        self.library.get().is_some()
+    }
+
+    /// Opens the library if needed and obtains a symbol, like [`Library::symbol`].
+    pub fn symbol<T>(&self, name: &CStr) -> Result<Symbol<'_, T>> {
+        self.get()?.symbol(name)
+    }
+
+    /// Opens the library if needed and obtains a debug symbol, like
+    /// [`Library::debug_symbol`].
+    pub fn debug_symbol<T>(&self, name: &CStr) -> Result<Symbol<'_, T>> {
+        self.get()?.debug_symbol(name)
+    }
+}
+
+/// Where a [`LazySymbol`] is looked up.
+#[derive(Debug, Clone, Copy)]
+enum Source<'lib> {
+    Library(&'lib Library),
+    Lazy(&'lib LazyLibrary),
+    /// The handle of a [`raw::Library`](RowLibrary), which is moved together with the symbol,
+    /// e.g. in a [`Container`](crate::wrapper::Container).
+    Handle(Handle),
+}
+
+/// A symbol that is looked up on first use and cached.
+///
+/// Lookups that fail are not cached: [`get`](Self::get) returns the error and the next call
+/// tries again.
+#[derive(Debug)]
+pub struct LazySymbol<'lib, T> {
+    source: Source<'lib>,
+    name: &'lib CStr,
+    debug: bool,
+    address: OnceLock<usize>,
+    _phantom: PhantomData<&'lib T>,
+}
+
+impl<'lib, T> LazySymbol<'lib, T> {
+    /// A symbol of `library`, which is opened when the symbol is first used.
+    pub const fn new(library: &'lib LazyLibrary, name: &'lib CStr) -> Self {
+        Self::from_source(Source::Lazy(library), name, false)
+    }
+
+    /// Like [`new`](Self::new), but looks the symbol up in `.symtab`, like
+    /// [`Library::debug_symbol`].
+    pub const fn debug(library: &'lib LazyLibrary, name: &'lib CStr) -> Self {
+        Self::from_source(Source::Lazy(library), name, true)
+    }
+
+    /// A symbol of a [`raw::Library`](RowLibrary), as used by `#[native(lazy)]` fields.
+    ///
+    /// # Safety
+    /// The symbol must not be used after `library` is dropped, and `T` must match the type of
+    /// the symbol, as with [`raw::Library::symbol`](RowLibrary::symbol).
+    pub unsafe fn from_raw(library: &RowLibrary, name: &'lib CStr, debug: bool) -> Self {
+        Self::from_source(Source::Handle(unsafe { library.handle() }), name, debug)
+    }
+
+    const fn from_source(source: Source<'lib>, name: &'lib CStr, debug: bool) -> Self {
+        Self {
+            source,
+            name,
+            debug,
+            address: OnceLock::new(),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub fn name(&self) -> &'lib CStr {
+        self.name
+    }
+
+    /// Returns `true` if the symbol has been looked up.
+    pub fn is_resolved(&self) -> bool {
+        self.address.get().is_some()
+    }
+
+    /// Returns the symbol, looking it up if this is the first call.
+    pub fn get(&self) -> Result<T>
+    where
+        T: Copy,
+    {
+        assert_type_size::<T>();
+        let address = match self.address.get() {
+            Some(&address) => address,
+            None => {
+                let address = self.resolve()?;
+                *self.address.get_or_init(|| address)
+            }
+        };
+        Ok(unsafe { transmute_copy(&(address as *mut c_void)) })
+    }
+
+    fn resolve(&self) -> Result<usize> {
+        let library = match self.source {
+            Source::Library(library) => library,
+            Source::Lazy(library) => library.get()?,
+            Source::Handle(handle) => {
+                // Borrows the handle: it is closed by the library it was taken from.
+                let library = ManuallyDrop::new(unsafe { RowLibrary::new(handle)? });
+                // The same lookup as eager fields, which runs IFUNC resolvers.
+                let symbol = library.resolve(&SymbolRequest {
+                    name: self.name,
+                    debug: self.debug,
+                    hash: None,
+                })?;
+                return Ok(symbol.address.as_ptr() as usize);
+            }
+        };
+        let symbol = match self.debug {
+            true => library.debug_symbol::<*mut c_void>(self.name)?,
+            false => library.symbol::<*mut c_void>(self.name)?,
+        };
+        Ok(*symbol as usize)
+    }
+}
+
+unsafe impl<'lib, T: Send> Send for LazySymbol<'lib, T> {}
+unsafe impl<'lib, T: Sync> Sync for LazySymbol<'lib, T> {}
+
+impl Library {
+    /// Returns a symbol that is looked up on first use, like [`symbol`](Self::symbol).
+    pub fn lazy_symbol<'lib, T>(&'lib self, name: &'lib CStr) -> LazySymbol<'lib, T> {
+        LazySymbol::from_source(Source::Library(self), name, false)
+    }
+
+    /// Returns a symbol that is looked up in `.symtab` on first use, like
+    /// [`debug_symbol`](Self::debug_symbol).
+    pub fn lazy_debug_symbol<'lib, T>(&'lib self, name: &'lib CStr) -> LazySymbol<'lib, T> {
+        LazySymbol::from_source(Source::Library(self), name, true)
+    }
+}
@@ -1,8 +1,20 @@
 use crate::Error;
+use crate::apk;
+use crate::elf::{AndroidIdent, BuildId, Dependencies, Image, Relocations};
+use crate::module::loaded_modules;
 use crate::raw::Library as RowLibrary;
-use crate::symbol::Symbol;
-use std::ffi::CStr;
+use crate::raw::api::XDL_TRY_FORCE_LOAD;
+use crate::raw::assert_type_size;
+use crate::raw::{
+    HandleOwnership, OpenOptions, ResolvedSymbol, SymbolHash, SymbolKind, SymbolRequest,
+};
+use crate::symbol::{Symbol, SymbolCache};
+use std::ffi::{CStr, CString};
+use std::mem::transmute_copy;
 use std::ops::Deref;
+use std::os::raw::c_void;
+use std::path::Path;
+use std::ptr::NonNull;
 
 type Result<T> = std::result::Result<T, Error>;
 
@@ -16,6 +28,8 @@ dangling symbols is prevented.
 #[derive(Debug)]
 pub struct Library {
     lib: RowLibrary,
+    /// Boxed, as most libraries do not enable it.
+    cache: Option<Box<SymbolCache>>,
 }
 
 impl Library {
@@ -30,34 +44,277 @@ impl Library {
         unsafe { RowLibrary::open_with_flags(name.as_ref(), flags).map(Self::from) }
     }
 
+    /// Loads a library with `android_dlopen_ext`, see [`OpenOptions`].
+    ///
+    /// Unlike [`open_with_flags`](Self::open_with_flags), this can choose the linker namespace,
+    /// a reserved address range or a file descriptor to load from.
+    ///
+    /// # Safety
+    /// A [`Namespace::Raw`](crate::Namespace::Raw) must point to a linker namespace, a
+    /// reserved region must be mapped memory the library may be loaded over, and a library fd
+    /// must stay open until the call returns, see
+    /// [`raw::Library::open_with`](RowLibrary::open_with).
+    pub unsafe fn open_with<S: AsRef<CStr>>(name: S, options: &OpenOptions) -> Result<Self> {
+        unsafe { RowLibrary::open_with(name.as_ref(), options).map(Self::from) }
+    }
+
+    /// Opens every loaded instance of `name` (a basename or full path), in load order.
+    ///
+    /// `xdl_open` only returns the first instance; use this or
+    /// [`DuplicatePolicy`](crate::DuplicatePolicy) when a library may be loaded more than once.
+    pub fn open_all<S: AsRef<CStr>>(name: S) -> Vec<Self> {
+        RowLibrary::open_all(name.as_ref())
+            .into_iter()
+            .map(Self::from)
+            .collect()
+    }
+
+    /// Loads a library from an ELF image in memory, see [`RowLibrary::open_bytes`].
+    ///
+    /// Useful for libraries that are downloaded or decrypted at run time: nothing is written
+    /// to disk.
+    pub fn open_bytes<S: AsRef<CStr>>(bytes: &[u8], name: S) -> Result<Self> {
+        unsafe { RowLibrary::open_bytes(bytes, name.as_ref()).map(Self::from) }
+    }
+
+    /// Wraps a handle returned by `dlopen`, see [`RowLibrary::from_dlopen_handle`].
+    ///
+    /// # Safety
+    /// `handle` must be a live handle returned by the linker, closed according to
+    /// `ownership`.
+    pub unsafe fn from_dlopen_handle(
+        handle: *mut c_void,
+        ownership: HandleOwnership,
+    ) -> Result<Self> {
+        unsafe { RowLibrary::from_dlopen_handle(handle, ownership).map(Self::from) }
+    }
+
+    /// Opens a library stored uncompressed inside an APK (`extractNativeLibs=false`).
+    ///
+    /// `entry` is the name of the ZIP entry, e.g. `lib/arm64-v8a/libfoo.so`, or only the
+    /// basename to match any ABI directory. A module the linker reports as `<apk>!/<entry>` is
+    /// opened without loading it again. Otherwise the library is loaded with `dlopen`, which
+    /// requires the full entry name.
+    ///
+    /// [`debug_symbol`](Self::debug_symbol) reads `.symtab` and `.gnu_debugdata` from the stored
+    /// entry.
+    pub fn open_in_apk<P: AsRef<Path>>(apk: P, entry: &str) -> Result<Self> {
+        let apk = apk.as_ref().to_string_lossy();
+        let prefix = format!("{}{}", apk, apk::SEPARATOR);
+        let entry = entry.trim_start_matches('/');
+        let module = loaded_modules().into_iter().find(|module| {
+            let path = module.path().to_string_lossy();
+            path.strip_prefix(&prefix).is_some_and(|name| {
+                name == entry || (!entry.contains('/') && name.rsplit('/').next() == Some(entry))
+            })
+        });
+        if let Some(module) = module {
+            return module.open();
+        }
+        if !entry.contains('/') {
+            return Err(Error::OpeningLibraryError(format!(
+                "`{}` is not loaded from `{}`",
+                entry, apk
+            )));
+        }
+        let path = CString::new(format!("{}{}", prefix, entry)).map_err(|_| {
+            Error::OpeningLibraryError("The path must not contain a NUL byte.".to_string())
+        })?;
+        Self::open_with_flags(path, XDL_TRY_FORCE_LOAD)
+    }
+
     /// Obtains a symbol from the opened library.
+    ///
+    /// For an indirect function (`STT_GNU_IFUNC`), the resolver is run and the implementation
+    /// it selects is returned, as with `dlsym`; see [`symbol_resolved`](Self::symbol_resolved).
+    /// The type of the symbol comes from the same `.dynsym` lookup as its address.
     pub fn symbol<T>(&self, name: &CStr) -> Result<Symbol<'_, T>> {
-        unsafe { self.lib.symbol(name, None).map(Symbol::new) }
+        self.lookup(name, false)
+            .map(|(address, _)| Symbol::new(bind(address)))
     }
 
     /// Obtains a symbol and size from the opened library.
     pub fn symbol_and_size<T>(&self, name: &CStr) -> Result<(Symbol<'_, T>, usize)> {
-        unsafe {
-            let mut symbol_size: usize = 0;
-            self.lib
-                .symbol(name, Some(&mut symbol_size))
-                .map(|sym| (Symbol::new(sym), symbol_size))
-        }
+        self.lookup(name, false)
+            .map(|(address, size)| (Symbol::new(bind(address)), size))
+    }
+
+    /// Obtains a symbol after checking its `.dynsym` entry against `T`, see
+    /// [`SymbolKind`](crate::SymbolKind).
+    ///
+    /// Catches binding a function to a data type (or the reverse) and binding an object
+    /// smaller than the referenced type.
+    pub fn symbol_checked<T: SymbolKind>(&self, name: &CStr) -> Result<Symbol<'_, T>> {
+        self.lib.check_kind::<T>(name)?;
+        self.lookup(name, false)
+            .map(|(address, _)| Symbol::new(bind(address)))
+    }
+
+    /// Obtains a symbol with the hashes of its name computed in advance, see
+    /// [`RowLibrary::symbol_prehashed`].
+    ///
+    /// The hashes are usually computed at compile time with
+    /// [`SymbolHash::of`](crate::SymbolHash::of) in a `const`; `hash` must be the hash of
+    /// `name`.
+    pub fn symbol_prehashed<T>(&self, name: &CStr, hash: SymbolHash) -> Result<Symbol<'_, T>> {
+        let request = SymbolRequest {
+            name,
+            debug: false,
+            hash: Some(hash),
+        };
+        self.cached(name, false, || self.resolve(&request))
+            .map(|(address, _)| Symbol::new(bind(address)))
+    }
+
+    /// Looks up many symbols at once, see [`RowLibrary::resolve_batch`].
+    ///
+    /// With the cache enabled, cached symbols are not looked up again, and the symbols the
+    /// batch finds are added to the cache.
+    pub fn resolve_batch(&self, requests: &[SymbolRequest]) -> Vec<Result<ResolvedSymbol>> {
+        let Some(cache) = &self.cache else {
+            return self.lib.resolve_batch(requests);
+        };
+        let cached: Vec<_> = requests
+            .iter()
+            .map(|request| {
+                let (address, size) = cache.get(request.name, request.debug)?;
+                NonNull::new(address as *mut c_void).map(|address| ResolvedSymbol { address, size })
+            })
+            .collect();
+        let misses: Vec<_> = requests
+            .iter()
+            .zip(&cached)
+            .filter(|(_, cached)| cached.is_none())
+            .map(|(request, _)| *request)
+            .collect();
+        let mut resolved = self.lib.resolve_batch(&misses).into_iter();
+        requests
+            .iter()
+            .zip(cached)
+            .map(|(request, cached)| match cached {
+                Some(symbol) => Ok(symbol),
+                None => {
+                    let result = resolved.next().expect("one result per request");
+                    if let Ok(symbol) = &result {
+                        let address = symbol.address.as_ptr() as usize;
+                        cache.insert(request.name, request.debug, address, symbol.size);
+                    }
+                    result
+                }
+            })
+            .collect()
     }
 
     /// Obtains a debug symbol from the opened library.
+    ///
+    /// `.symtab` and `.gnu_debugdata` are read from the library file (or the stored ZIP entry,
+    /// for a library loaded from inside an APK) and indexed on the first call, then reused by
+    /// every debug lookup on this library.
+    ///
+    /// For an indirect function (`STT_GNU_IFUNC`), the resolver is run and the implementation
+    /// it selects is returned; see [`debug_symbol_resolved`](Self::debug_symbol_resolved).
     pub fn debug_symbol<T>(&self, name: &CStr) -> Result<Symbol<'_, T>> {
-        unsafe { self.lib.debug_symbol(name, None).map(Symbol::new) }
+        self.lookup(name, true)
+            .map(|(address, _)| Symbol::new(bind(address)))
     }
 
     /// Obtains a debug symbol and size from the opened library.
     pub fn debug_symbol_and_size<T>(&self, name: &CStr) -> Result<(Symbol<'_, T>, usize)> {
-        unsafe {
-            let mut symbol_size: usize = 0;
-            self.lib
-                .debug_symbol(name, Some(&mut symbol_size))
-                .map(|sym| (Symbol::new(sym), symbol_size))
+        self.lookup(name, true)
+            .map(|(address, size)| (Symbol::new(bind(address)), size))
+    }
+
+    /// Enables the symbol cache: later lookups of the same name by [`symbol`](Self::symbol),
+    /// [`debug_symbol`](Self::debug_symbol), their `_and_size` variants,
+    /// [`symbol_checked`](Self::symbol_checked), [`symbol_prehashed`](Self::symbol_prehashed)
+    /// and [`resolve_batch`](Self::resolve_batch) skip the lookup in the symbol tables.
+    pub fn with_cache(mut self) -> Self {
+        self.cache.get_or_insert_with(Box::default);
+        self
+    }
+
+    /// Returns the symbol cache, if enabled with [`with_cache`](Self::with_cache).
+    pub fn cache(&self) -> Option<&SymbolCache> {
+        self.cache.as_deref()
+    }
+
+    /// Looks up `names` in advance, filling the cache. Returns the number of symbols found.
+    ///
+    /// With `debug`, the names are looked up in `.symtab`, as by
+    /// [`debug_symbol`](Self::debug_symbol). Without a cache (see
+    /// [`with_cache`](Self::with_cache)), nothing is looked up and 0 is returned.
+    pub fn prewarm<'a, I>(&self, names: I, debug: bool) -> usize
+    where
+        I: IntoIterator<Item = &'a CStr>,
+    {
+        if self.cache.is_none() {
+            return 0;
         }
+        names
+            .into_iter()
+            .filter(|name| self.lookup(name, debug).is_ok())
+            .count()
+    }
+
+    /// Looks up the address and size of a symbol, through the cache if enabled.
+    fn lookup(&self, name: &CStr, debug: bool) -> Result<(*mut c_void, usize)> {
+        let request = SymbolRequest {
+            name,
+            debug,
+            hash: None,
+        };
+        self.cached(name, debug, || self.resolve(&request))
+    }
+
+    /// Looks up a symbol with a single search of the symbol table, running the resolver of an
+    /// indirect function.
+    fn resolve(&self, request: &SymbolRequest) -> Result<(*mut c_void, usize)> {
+        self.lib
+            .resolve(request)
+            .map(|symbol| (symbol.address.as_ptr(), symbol.size))
+    }
+
+    /// Returns the cached address and size of a symbol, or looks it up with `resolve` and
+    /// caches it.
+    fn cached<F>(&self, name: &CStr, debug: bool, resolve: F) -> Result<(*mut c_void, usize)>
+    where
+        F: FnOnce() -> Result<(*mut c_void, usize)>,
+    {
+        match &self.cache {
+            Some(cache) => cache.get_or_resolve(name, debug, resolve),
+            None => resolve(),
+        }
+    }
+
+    /// Decodes the relocation tables of the library: `DT_REL`, `DT_RELA`, `DT_JMPREL`, `DT_RELR`
+    /// and Android's packed `DT_ANDROID_REL` / `DT_ANDROID_RELA`.
+    ///
+    /// The tables are read from memory; use [`ElfFile`](crate::elf::ElfFile) for a file on disk.
+    pub fn relocations(&self) -> Result<Relocations> {
+        unsafe { Image::from_library(&self.lib)?.relocations() }
+    }
+
+    /// Reads the dependencies declared by the library (`DT_NEEDED`, `DT_SONAME`, `DT_RUNPATH`).
+    ///
+    /// Use [`DependencyGraph`](crate::graph::DependencyGraph) to find the loaded instance of
+    /// each dependency.
+    pub fn dependencies(&self) -> Result<Dependencies> {
+        unsafe { Image::from_library(&self.lib)?.dependencies() }
+    }
+
+    /// Reads the `NT_GNU_BUILD_ID` note from the `PT_NOTE` segments of the library.
+    ///
+    /// Returns `Ok(None)` if the library was linked without `--build-id`. Use
+    /// [`ElfFile::build_id`](crate::elf::ElfFile::build_id) for a file on disk.
+    pub fn build_id(&self) -> Result<Option<BuildId>> {
+        unsafe { Ok(Image::from_library(&self.lib)?.build_id()) }
+    }
+
+    /// Reads the NDK version and target API level from `.note.android.ident`.
+    ///
+    /// Returns `Ok(None)` if the library was not built with the NDK.
+    pub fn android_ident(&self) -> Result<Option<AndroidIdent>> {
+        unsafe { Ok(Image::from_library(&self.lib)?.android_ident()) }
     }
 }
 
@@ -71,6 +328,15 @@ impl Deref for Library {
 
 impl From<RowLibrary> for Library {
     fn from(value: RowLibrary) -> Self {
-        Self { lib: value }
+        Self {
+            lib: value,
+            cache: None,
+        }
     }
 }
+
+/// Converts a symbol address to `T`, which must have the size of a pointer.
+fn bind<T>(address: *mut c_void) -> T {
+    assert_type_size::<T>();
+    unsafe { transmute_copy(&address) }
+}
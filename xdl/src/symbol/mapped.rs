@@ -0,0 +1,262 @@
+use super::Library;
+use crate::Error;
+use crate::elf::{Class, EM_NATIVE, Header, PF_R, PF_X, PT_LOAD, PT_PHDR};
+use crate::maps::{self, Mapping};
+use crate::module::{Phdr, loaded_modules};
+use crate::raw::Library as RowLibrary;
+use crate::raw::api::*;
+use std::ffi::CString;
+use std::path::Path;
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Identifies an ELF image mapped into the process, see [`Library::open_mapped`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MappedImage {
+    /// Path of the mapped file (or only its basename), or a pseudo-name such as `[vdso]`.
+    Path(String),
+    /// Any address within the image.
+    Address(usize),
+}
+
+impl From<&str> for MappedImage {
+    fn from(path: &str) -> Self {
+        MappedImage::Path(path.to_string())
+    }
+}
+
+impl From<String> for MappedImage {
+    fn from(path: String) -> Self {
+        MappedImage::Path(path)
+    }
+}
+
+impl From<&Path> for MappedImage {
+    fn from(path: &Path) -> Self {
+        MappedImage::Path(path.to_string_lossy().into_owned())
+    }
+}
+
+impl From<usize> for MappedImage {
+    fn from(address: usize) -> Self {
+        MappedImage::Address(address)
+    }
+}
+
+impl<T> From<*const T> for MappedImage {
+    fn from(address: *const T) -> Self {
+        MappedImage::Address(address as usize)
+    }
+}
+
+impl<T> From<*mut T> for MappedImage {
+    fn from(address: *mut T) -> Self {
+        MappedImage::Address(address as usize)
+    }
+}
+
+impl Library {
+    /// Opens an ELF image that is mapped into the process, even if the linker does not know
+    /// about it (custom loaders, `mmap`'ed images).
+    ///
+    /// The mapping is found through `/proc/self/maps`, and the ELF header and program headers
+    /// are read from memory to build the `dl_phdr_info` passed to `xdl_open2`. A mapping is
+    /// only accepted if every `PT_LOAD` segment is mapped where the program headers place it,
+    /// from the right file offset and with matching protections, so a flat `mmap` of the file
+    /// is skipped. A module known to the linker is opened as usual.
+    ///
+    /// [`debug_symbol`](Self::debug_symbol) only works if the image is backed by a file.
+    ///
+    /// # Safety
+    /// The image must be loaded, i.e. mapped segment by segment and relocated like the linker
+    /// does, and must stay mapped for the duration of the call and for as long as the returned
+    /// library is alive. The checks above rule out common mistakes, but can not prove it.
+    pub unsafe fn open_mapped<T: Into<MappedImage>>(image: T) -> Result<Self> {
+        let image = image.into();
+        let error = |msg: &str| Error::OpeningLibraryError(format!("{:?}: {}", image, msg));
+        let mappings = maps::read()?;
+        let mut result = Err("no ELF header found");
+        for mapping in headers(&mappings, &image) {
+            result = unsafe { LoadedImage::parse(&mappings, mapping) };
+            if result.is_ok() {
+                break;
+            }
+        }
+        let loaded = result.map_err(error)?;
+
+        let known = loaded_modules()
+            .into_iter()
+            .find(|module| module.base() == loaded.bias && module.phdr_address() == loaded.phdr);
+        if let Some(module) = known {
+            return module.open();
+        }
+
+        let name = if loaded.mapping.path.is_empty() {
+            format!("[anon:{:#x}]", loaded.mapping.start)
+        } else {
+            loaded.mapping.path.clone()
+        };
+        let name = CString::new(name).map_err(|_| error("invalid mapping name"))?;
+        unsafe {
+            let mut info: dl_phdr_info = std::mem::zeroed();
+            info.dlpi_addr = loaded.bias as _;
+            info.dlpi_name = name.as_ptr();
+            info.dlpi_phdr = loaded.phdr as *const Phdr;
+            info.dlpi_phnum = loaded.phnum as _;
+            RowLibrary::new(xdl_open2(&mut info))
+                .map(Self::from)
+                .map_err(|_| error("xdl_open2 failed"))
+        }
+    }
+}
+
+/// An image found by [`Library::open_mapped`], its layout checked against the mappings.
+struct LoadedImage<'a> {
+    /// The mapping starting with the ELF header.
+    mapping: &'a Mapping,
+    bias: usize,
+    phdr: usize,
+    phnum: u16,
+}
+
+impl<'a> LoadedImage<'a> {
+    /// Reads the headers at the start of `mapping`.
+    ///
+    /// # Safety
+    /// `mapping` must still be mapped.
+    unsafe fn parse(
+        mappings: &[Mapping],
+        mapping: &'a Mapping,
+    ) -> std::result::Result<Self, &'static str> {
+        let ehdr = mapping.start;
+        let bytes = unsafe { std::slice::from_raw_parts(ehdr as *const u8, mapping.end - ehdr) };
+        let header = Header::parse(bytes).map_err(|_| "invalid ELF header")?;
+        if header.class != Class::NATIVE || header.machine != EM_NATIVE {
+            return Err("the image is built for another architecture");
+        }
+        let table_size = header.phnum as u64 * header.phentsize as u64;
+        if header.phentsize as usize != size_of::<Phdr>()
+            || header
+                .phoff
+                .checked_add(table_size)
+                .is_none_or(|end| end > bytes.len() as u64)
+        {
+            return Err("the program headers are not mapped");
+        }
+        let phdrs = unsafe {
+            std::slice::from_raw_parts(
+                (ehdr + header.phoff as usize) as *const Phdr,
+                header.phnum as usize,
+            )
+        };
+
+        // The ELF header is at file offset 0, mapped by the first PT_LOAD segment.
+        let bias = if header.e_type == Header::ET_EXEC {
+            0
+        } else {
+            let load = phdrs
+                .iter()
+                .find(|phdr| phdr.p_type == PT_LOAD)
+                .ok_or("no PT_LOAD segment")?;
+            ehdr.wrapping_sub((load.p_vaddr as usize).wrapping_sub(load.p_offset as usize))
+        };
+        if !segments_mapped(mappings, mapping, bias, phdrs) {
+            return Err("the mappings do not match the PT_LOAD segments");
+        }
+        let phdr = phdrs
+            .iter()
+            .find(|phdr| phdr.p_type == PT_PHDR)
+            .map_or(ehdr + header.phoff as usize, |phdr| {
+                bias.wrapping_add(phdr.p_vaddr as usize)
+            });
+        Ok(Self {
+            mapping,
+            bias,
+            phdr,
+            phnum: header.phnum,
+        })
+    }
+}
+
+/// Checks that every `PT_LOAD` segment is mapped at `bias` like the linker maps it: fully
+/// covered, executable exactly if the segment is, readable if it is, and for an image backed
+/// by a file, mapped from the file offset of the segment.
+///
+/// Writability is not compared, RELRO segments are read-only after relocation.
+fn segments_mapped(mappings: &[Mapping], header: &Mapping, bias: usize, phdrs: &[Phdr]) -> bool {
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+    let file_backed = header.path.starts_with('/');
+    let find = |address: usize| mappings.iter().find(|mapping| mapping.contains(address));
+    phdrs
+        .iter()
+        .filter(|phdr| phdr.p_type == PT_LOAD && phdr.p_memsz != 0)
+        .all(|phdr| {
+            let vaddr = bias.wrapping_add(phdr.p_vaddr as usize);
+            let start = vaddr & !(page_size - 1);
+            let Some(end) = vaddr.checked_add(phdr.p_memsz as usize) else {
+                return false;
+            };
+            let Some(first) = find(start) else {
+                return false;
+            };
+            let from_file = !file_backed
+                || phdr.p_filesz == 0
+                || (first.path == header.path
+                    && first.offset as usize + (start - first.start)
+                        == phdr.p_offset as usize & !(page_size - 1));
+            let protection = first.executable == (phdr.p_flags & PF_X != 0)
+                && (first.readable || phdr.p_flags & PF_R == 0);
+            let mut covered = first.end;
+            while covered < end {
+                match find(covered) {
+                    Some(next) => covered = next.end,
+                    None => return false,
+                }
+            }
+            from_file && protection
+        })
+}
+
+/// Finds the readable mappings starting with an ELF header that may belong to `image`.
+fn headers<'a>(
+    mappings: &'a [Mapping],
+    image: &MappedImage,
+) -> Box<dyn Iterator<Item = &'a Mapping> + 'a> {
+    let has_header = |mapping: &&Mapping| {
+        mapping.readable
+            && mapping.offset == 0
+            && mapping.end - mapping.start >= 4
+            && unsafe { std::slice::from_raw_parts(mapping.start as *const u8, 4) } == b"\x7fELF"
+    };
+    match image {
+        MappedImage::Path(path) => {
+            let path = path.clone();
+            Box::new(
+                mappings
+                    .iter()
+                    .filter(move |mapping| {
+                        if path.contains('/') {
+                            mapping.path == path
+                        } else {
+                            mapping.path.rsplit('/').next() == Some(path.as_str())
+                        }
+                    })
+                    .filter(has_header),
+            )
+        }
+        MappedImage::Address(address) => {
+            let Some(index) = mappings.iter().position(|m| m.contains(*address)) else {
+                return Box::new(std::iter::empty());
+            };
+            let path = &mappings[index].path;
+            // Walk back over the segments of the same file (or the same anonymous region).
+            Box::new(
+                mappings[..=index]
+                    .iter()
+                    .rev()
+                    .take_while(move |mapping| mapping.path == *path)
+                    .filter(has_header),
+            )
+        }
+    }
+}
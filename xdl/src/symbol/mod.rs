@@ -1,5 +1,25 @@
+mod cache;
+mod data;
+mod global;
+mod ifunc;
+mod imports;
+mod lazy;
 mod library;
+mod mapped;
+mod shared;
+mod special;
+mod tls;
+mod wait;
+pub use global::{AtomicPrimitive, GlobalVar};
+pub use cache::{CacheStats, CachedSymbol, SymbolCache};
+pub use ifunc::{IFunc, Resolved};
+pub use imports::{Import, ImportTarget};
+pub use lazy::{LazyLibrary, LazySymbol};
 pub use library::*;
+pub use mapped::MappedImage;
+pub use shared::{OwnedSymbol, SharedLibrary};
+pub use tls::TlsSymbol;
+pub use wait::WaitFor;
 
 use std::marker::PhantomData;
 use std::mem::transmute_copy;
@@ -32,6 +52,18 @@ impl<'lib, T> Symbol<'lib, T> {
             Some(Self::new(unsafe { transmute_copy(&ptr) }))
         }
     }
+
+    /// Converts the symbol into one that keeps `library` open, see [`SharedLibrary::own`].
+    pub fn into_owned(
+        self,
+        library: &'lib SharedLibrary,
+    ) -> Result<OwnedSymbol<T>, crate::Error> {
+        library.own(self)
+    }
+
+    pub(crate) fn into_inner(self) -> T {
+        self.symbol
+    }
 }
 
 impl<'lib, T> Deref for Symbol<'lib, T> {
@@ -0,0 +1,119 @@
+use super::{Library, Symbol};
+use crate::Error;
+use crate::raw::assert_type_size;
+use std::ffi::CStr;
+use std::mem::transmute_copy;
+use std::ops::Deref;
+use std::os::raw::c_void;
+use std::sync::Arc;
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// A [`Library`] that can be cloned cheaply and shared between threads.
+///
+/// The module is closed when the last clone and the last [`OwnedSymbol`] obtained from it
+/// are dropped.
+#[derive(Debug, Clone)]
+pub struct SharedLibrary {
+    lib: Arc<Library>,
+}
+
+impl SharedLibrary {
+    pub fn new(lib: Library) -> Self {
+        Self { lib: Arc::new(lib) }
+    }
+
+    /// Obtains a symbol that keeps the library open.
+    pub fn symbol<T>(&self, name: &CStr) -> Result<OwnedSymbol<T>> {
+        self.lib
+            .symbol(name)
+            .map(|symbol| self.own_unchecked(symbol))
+    }
+
+    /// Obtains a debug symbol that keeps the library open.
+    pub fn debug_symbol<T>(&self, name: &CStr) -> Result<OwnedSymbol<T>> {
+        self.lib
+            .debug_symbol(name)
+            .map(|symbol| self.own_unchecked(symbol))
+    }
+
+    /// Converts a symbol borrowed from this library into one that keeps the library open.
+    ///
+    /// Returns [`Error::InvalidSymbol`] if the address of `symbol` is not within the loaded
+    /// segments of this library, e.g. for a symbol obtained from another library.
+    pub fn own<'lib, T>(&'lib self, symbol: Symbol<'lib, T>) -> Result<OwnedSymbol<T>> {
+        assert_type_size::<T>();
+        let address = unsafe { transmute_copy::<T, *const c_void>(&*symbol) };
+        if !self.module()?.contains(address as usize) {
+            return Err(Error::InvalidSymbol(format!(
+                "{:p} does not belong to the library",
+                address
+            )));
+        }
+        Ok(self.own_unchecked(symbol))
+    }
+
+    fn own_unchecked<T>(&self, symbol: Symbol<'_, T>) -> OwnedSymbol<T> {
+        OwnedSymbol {
+            symbol: symbol.into_inner(),
+            library: self.clone(),
+        }
+    }
+}
+
+impl Deref for SharedLibrary {
+    type Target = Library;
+
+    fn deref(&self) -> &Self::Target {
+        &self.lib
+    }
+}
+
+impl From<Library> for SharedLibrary {
+    fn from(value: Library) -> Self {
+        Self::new(value)
+    }
+}
+
+impl Library {
+    /// Moves the library behind an [`Arc`], see [`SharedLibrary`].
+    pub fn into_shared(self) -> SharedLibrary {
+        SharedLibrary::new(self)
+    }
+}
+
+/// A symbol holding a strong reference to its library, see [`SharedLibrary::symbol`].
+///
+/// Unlike [`Symbol`], it has no lifetime: it can be stored in long-lived structs or statics
+/// and sent across threads, and the library stays open as long as it is alive.
+#[derive(Debug, Clone)]
+pub struct OwnedSymbol<T> {
+    symbol: T,
+    library: SharedLibrary,
+}
+
+impl<T> OwnedSymbol<T> {
+    /// The library the symbol belongs to.
+    pub fn library(&self) -> &SharedLibrary {
+        &self.library
+    }
+
+    /// Borrows the symbol as a [`Symbol`] tied to this value.
+    pub fn as_symbol(&self) -> Symbol<'_, T>
+    where
+        T: Copy,
+    {
+        Symbol::new(self.symbol)
+    }
+}
+
+impl<T> Deref for OwnedSymbol<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.symbol
+    }
+}
+
+unsafe impl<T: Send> Send for OwnedSymbol<T> {}
+unsafe impl<T: Sync> Sync for OwnedSymbol<T> {}
@@ -0,0 +1,87 @@
+use super::Library;
+use crate::Error;
+use crate::module::{ModuleInfo, loaded_modules};
+use crate::raw::api::*;
+use std::os::raw::{c_ulong, c_void};
+
+type Result<T> = std::result::Result<T, Error>;
+
+const AT_SYSINFO_EHDR: c_ulong = 33;
+
+#[cfg(target_pointer_width = "64")]
+const LINKER: &str = "linker64";
+#[cfg(target_pointer_width = "32")]
+const LINKER: &str = "linker";
+
+impl Library {
+    /// Opens the module containing `address`, as found by `xdl_addr`.
+    pub fn containing(address: *const c_void) -> Result<Self> {
+        let base = unsafe {
+            let mut info: xdl_info_t = std::mem::zeroed();
+            let mut cache = std::ptr::null_mut();
+            let found = xdl_addr4(address as *mut c_void, &mut info, &mut cache, XDL_NON_SYM);
+            xdl_addr_clean(&mut cache);
+            if found == 0 {
+                return Err(Error::OpeningLibraryError(format!(
+                    "no loaded module contains {:p}",
+                    address
+                )));
+            }
+            info.dli_fbase as usize
+        };
+        open_module(&format!("the module containing {:p}", address), |module| {
+            module.base() == base && module.contains(address as usize)
+        })
+    }
+
+    /// Opens the module this code is linked into, e.g. the app's own `.so`.
+    ///
+    /// Useful to look up the module's own debug symbols.
+    pub fn current() -> Result<Self> {
+        Self::containing(Self::current as fn() -> Result<Self> as *const c_void)
+    }
+
+    /// Opens the main executable of the process (`app_process64` for an app).
+    pub fn main_program() -> Result<Self> {
+        let phdr = unsafe { libc::getauxval(libc::AT_PHDR) } as usize;
+        // Without the auxiliary vector, rely on the executable being reported first.
+        open_module("the main executable", |module| {
+            phdr == 0 || module.phdr_address() == phdr
+        })
+    }
+
+    /// Opens the dynamic linker (`linker64` or `linker`).
+    pub fn linker() -> Result<Self> {
+        let base = unsafe { libc::getauxval(libc::AT_BASE) } as usize;
+        open_module(LINKER, |module| {
+            if base != 0 {
+                module.base() == base
+            } else {
+                module.matches(LINKER)
+            }
+        })
+    }
+
+    /// Opens the virtual dynamic shared object mapped by the kernel (`[vdso]`).
+    pub fn vdso() -> Result<Self> {
+        let ehdr = unsafe { libc::getauxval(AT_SYSINFO_EHDR) } as usize;
+        if ehdr == 0 {
+            return Err(Error::OpeningLibraryError(
+                "the kernel did not map a vDSO".to_string(),
+            ));
+        }
+        // Old linkers do not register the vDSO, open it from its mapping then. The kernel maps
+        // it as a loaded image, and it stays mapped for the lifetime of the process.
+        open_module("[vdso]", |module| module.contains(ehdr))
+            .or_else(|_| unsafe { Self::open_mapped(ehdr) })
+    }
+}
+
+/// Opens the first loaded module matching `predicate`.
+fn open_module<F: FnMut(&ModuleInfo) -> bool>(what: &str, predicate: F) -> Result<Library> {
+    loaded_modules()
+        .into_iter()
+        .find(predicate)
+        .ok_or_else(|| Error::OpeningLibraryError(format!("{} is not loaded", what)))?
+        .open()
+}
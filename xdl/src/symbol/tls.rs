@@ -0,0 +1,167 @@
+use super::Library;
+use crate::Error;
+use crate::elf::ElfSymbol;
+use crate::raw::Library as RowLibrary;
+use crate::raw::SymbolKind;
+use std::ffi::{CStr, CString};
+use std::marker::PhantomData;
+use std::os::raw::c_void;
+use std::sync::OnceLock;
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// `tls_index`, the argument of `__tls_get_addr`.
+#[repr(C)]
+struct TlsIndex {
+    module: usize,
+    offset: usize,
+}
+
+type TlsGetAddr = unsafe extern "C" fn(*const TlsIndex) -> *mut c_void;
+type EmutlsGetAddress = unsafe extern "C" fn(*mut c_void) -> *mut c_void;
+
+/// `__tls_get_addr` of libc, available since Android 10 (API 29).
+///
+/// 32-bit x86 only has `___tls_get_addr`, which takes its argument in a register.
+fn tls_get_addr() -> Option<TlsGetAddr> {
+    static TLS_GET_ADDR: OnceLock<Option<TlsGetAddr>> = OnceLock::new();
+    *TLS_GET_ADDR.get_or_init(|| {
+        if cfg!(target_arch = "x86") {
+            return None;
+        }
+        let ptr = unsafe { libc::dlsym(libc::RTLD_DEFAULT, c"__tls_get_addr".as_ptr()) };
+        (!ptr.is_null()).then(|| unsafe { std::mem::transmute::<*mut c_void, TlsGetAddr>(ptr) })
+    })
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Storage {
+    /// ELF TLS: a `STT_TLS` symbol at `offset` in the TLS block of `module`.
+    Native {
+        module: usize,
+        offset: usize,
+        get_addr: TlsGetAddr,
+    },
+    /// Emulated TLS: the `__emutls_v.<name>` control object of the variable.
+    Emulated {
+        control: *mut c_void,
+        get_address: EmutlsGetAddress,
+    },
+}
+
+/// A thread-local variable of a library, see [`Library::tls_symbol`].
+///
+/// `xdl_sym` returns the offset of a `STT_TLS` symbol in the TLS block, not an address:
+/// [`get`](Self::get) computes the address of the current thread's instance instead.
+#[derive(Debug, Clone, Copy)]
+pub struct TlsSymbol<'lib, T> {
+    storage: Storage,
+    _phantom: PhantomData<&'lib T>,
+}
+
+impl<'lib, T> TlsSymbol<'lib, T> {
+    /// Returns the address of the current thread's instance of the variable.
+    ///
+    /// The instance is allocated (and initialized) on first use by the thread. The pointer
+    /// is only valid on the calling thread, until it exits.
+    pub fn get(&self) -> *mut T {
+        unsafe {
+            match self.storage {
+                Storage::Native {
+                    module,
+                    offset,
+                    get_addr,
+                } => get_addr(&TlsIndex { module, offset }) as *mut T,
+                Storage::Emulated {
+                    control,
+                    get_address,
+                } => get_address(control) as *mut T,
+            }
+        }
+    }
+
+    /// Returns `true` if the variable uses emulated TLS (`-femulated-tls`, the default of
+    /// NDKs before r26 and of Android before 10).
+    pub fn is_emulated(&self) -> bool {
+        matches!(self.storage, Storage::Emulated { .. })
+    }
+}
+
+unsafe impl<'lib, T> Send for TlsSymbol<'lib, T> {}
+unsafe impl<'lib, T> Sync for TlsSymbol<'lib, T> {}
+
+impl<'lib, T> SymbolKind for TlsSymbol<'lib, T> {
+    fn check(symbol: &ElfSymbol) -> std::result::Result<(), String> {
+        if symbol.is_tls() {
+            Ok(())
+        } else {
+            Err("expected a thread-local variable".to_string())
+        }
+    }
+}
+
+impl Library {
+    /// Obtains a thread-local variable (`thread_local`, `__thread`), see [`TlsSymbol`].
+    ///
+    /// Both ELF TLS (a `STT_TLS` symbol, read with `__tls_get_addr` and the TLS module id of
+    /// the library) and emulated TLS (a `__emutls_v.<name>` control object, read with
+    /// the `__emutls_get_address` linked into the library) are supported.
+    ///
+    /// ELF TLS needs `__tls_get_addr`, which libc exports since Android 10 (API 29). On 32-bit
+    /// x86, libc only has `___tls_get_addr`, which takes its argument in a register and can not
+    /// be called from here: a `STT_TLS` symbol fails with [`Error::InvalidSymbol`] on x86, as it
+    /// does on older versions. Emulated TLS works everywhere.
+    pub fn tls_symbol<T>(&self, name: &CStr) -> Result<TlsSymbol<'_, T>> {
+        let error =
+            |msg: &str| Error::InvalidSymbol(format!("`{}`: {}", name.to_string_lossy(), msg));
+        let storage = match self.elf_symbol(name) {
+            Ok(symbol) => {
+                TlsSymbol::<T>::check(&symbol).map_err(|msg| error(&msg))?;
+                let module = self
+                    .module()?
+                    .tls_module_id()
+                    .ok_or_else(|| error("the linker reports no TLS module id for the library"))?;
+                let get_addr = tls_get_addr().ok_or_else(|| {
+                    error("`__tls_get_addr` is not available (Android 10+, not on x86)")
+                })?;
+                Storage::Native {
+                    module,
+                    offset: symbol.value as usize,
+                    get_addr,
+                }
+            }
+            Err(_) => {
+                let mut emulated = b"__emutls_v.".to_vec();
+                emulated.extend_from_slice(name.to_bytes());
+                let emulated = CString::new(emulated).expect("a CStr has no interior NUL");
+                let control =
+                    unsafe { RowLibrary::symbol::<*mut c_void>(self, &emulated, None) }
+                        .map_err(|_| Error::SymbolNotFound(name.to_string_lossy().to_string()))?;
+                Storage::Emulated {
+                    control,
+                    get_address: self.emutls_get_address().ok_or_else(|| {
+                        error("the library has no `__emutls_get_address` of its own")
+                    })?,
+                }
+            }
+        };
+        Ok(TlsSymbol {
+            storage,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Finds the `__emutls_get_address` the library uses: the runtime is linked into each
+    /// library, usually as a hidden symbol only present in `.symtab`.
+    ///
+    /// The runtime of another module has its own key and would allocate other instances for
+    /// the same control variable, so only the library that owns the variable is searched.
+    fn emutls_get_address(&self) -> Option<EmutlsGetAddress> {
+        let name = c"__emutls_get_address";
+        unsafe {
+            RowLibrary::symbol::<EmutlsGetAddress>(self, name, None)
+                .or_else(|_| RowLibrary::debug_symbol::<EmutlsGetAddress>(self, name, None))
+                .ok()
+        }
+    }
+}
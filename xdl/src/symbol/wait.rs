@@ -0,0 +1,122 @@
+use super::Library;
+use crate::Error;
+use crate::watch::{self, ModuleFilter, SharedPoller, WatchGuard};
+use std::ffi::{CStr, CString};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+type Result<T> = std::result::Result<T, Error>;
+
+impl Library {
+    /// Blocks until the library is loaded by someone else, then opens it with `XDL_DEFAULT`.
+    ///
+    /// The caller is woken up by the [module watcher](crate::watch) rather than by retrying
+    /// in a loop. Returns [`Error::WaitTimeout`] if the library did not appear within `timeout`.
+    ///
+    /// Called from a [watch callback](crate::watch), it would block the poll that is supposed
+    /// to wake it up: unless the library is already loaded, it fails right away with
+    /// [`Error::OpeningLibraryError`].
+    pub fn wait_for<S: AsRef<CStr>>(name: S, timeout: Duration) -> Result<Self> {
+        let name = name.as_ref();
+        if let Some(result) = try_open(name) {
+            return result;
+        }
+        if watch::is_dispatching() {
+            return Err(Error::OpeningLibraryError(format!(
+                "cannot wait for `{}` from inside a module watcher callback",
+                name.to_string_lossy()
+            )));
+        }
+        let deadline = Instant::now() + timeout;
+        let signal = Arc::new((Mutex::new(false), Condvar::new()));
+        let _watch = {
+            let signal = signal.clone();
+            WatchGuard(watch::on_module_loaded(filter(name), move |_, _| {
+                let (loaded, condvar) = &*signal;
+                *loaded.lock().unwrap_or_else(PoisonError::into_inner) = true;
+                condvar.notify_all();
+            }))
+        };
+        let _poller = watch::shared_poller();
+
+        let (loaded, condvar) = &*signal;
+        let mut guard = loaded.lock().unwrap_or_else(PoisonError::into_inner);
+        loop {
+            if *guard {
+                *guard = false;
+                if let Some(result) = try_open(name) {
+                    return result;
+                }
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(Error::WaitTimeout(
+                    name.to_string_lossy().to_string(),
+                    timeout,
+                ));
+            }
+            guard = condvar
+                .wait_timeout(guard, deadline - now)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+    }
+
+    /// Returns a future that resolves once the library is loaded by someone else.
+    ///
+    /// The future does not depend on any async runtime: it is woken up by a background
+    /// [module watcher](crate::watch) thread. It has no timeout of its own, combine it with the
+    /// timeout facility of your runtime if needed.
+    pub fn wait_for_async<S: AsRef<CStr>>(name: S) -> WaitFor {
+        WaitFor {
+            name: name.as_ref().to_owned(),
+            waker: Arc::new(Mutex::new(None)),
+            watch: None,
+        }
+    }
+}
+
+/// Future returned by [`Library::wait_for_async`].
+pub struct WaitFor {
+    name: CString,
+    waker: Arc<Mutex<Option<Waker>>>,
+    watch: Option<(WatchGuard, SharedPoller)>,
+}
+
+impl Future for WaitFor {
+    type Output = Result<Library>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // Store the waker before trying, so a load in between is not missed.
+        *self.waker.lock().unwrap_or_else(PoisonError::into_inner) = Some(cx.waker().clone());
+        if let Some(result) = try_open(&self.name) {
+            self.watch = None;
+            return Poll::Ready(result);
+        }
+        if self.watch.is_none() {
+            let waker = self.waker.clone();
+            let id = watch::on_module_loaded(filter(&self.name), move |_, _| {
+                if let Some(waker) = waker.lock().unwrap_or_else(PoisonError::into_inner).take() {
+                    waker.wake();
+                }
+            });
+            self.watch = Some((WatchGuard(id), watch::shared_poller()));
+        }
+        Poll::Pending
+    }
+}
+
+/// Returns `None` if the library is not loaded yet, otherwise the final result.
+fn try_open(name: &CStr) -> Option<Result<Library>> {
+    if name.is_empty() {
+        return Some(Library::open(name));
+    }
+    Library::open(name).ok().map(Ok)
+}
+
+fn filter(name: &CStr) -> ModuleFilter {
+    ModuleFilter::Name(name.to_string_lossy().to_string())
+}
@@ -0,0 +1,448 @@
+/*!
+Notifications for modules being loaded into and unloaded from the process.
+
+The watcher keeps a snapshot of the loaded modules and diffs it against the current state on each
+[`poll`]. On Android 11+ the linker's `dlpi_adds` / `dlpi_subs` counters are compared first, so
+a poll that finds nothing new does not walk the module list. They also reveal a module that was
+unloaded and loaded again at the same address between two polls, which is then reported as
+unloaded and loaded again. Polling happens either explicitly via
+[`poll`], or on a background thread started with [`spawn_poller`].
+
+# Example
+
+```no_run
+use android_xdl::watch::{self, ModuleFilter};
+use std::time::Duration;
+
+watch::on_module_loaded("libil2cpp.so", |info, lib| {
+    println!("loaded {:?} at {:#x}", info.path(), info.base());
+    if let Some(lib) = lib {
+        let _init = lib.symbol::<extern "C" fn()>(c"il2cpp_init");
+    }
+});
+watch::on_module_unloaded(ModuleFilter::Any, |info| {
+    println!("unloaded {:?}", info.path());
+});
+
+let poller = watch::spawn_poller(Duration::from_millis(100));
+// ...
+poller.stop();
+```
+*/
+use crate::Library;
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+pub use crate::module::ModuleInfo;
+
+/// Selects the modules a callback is interested in.
+pub enum ModuleFilter {
+    /// Every module.
+    Any,
+    /// Modules whose basename (or full path, if the name contains `/`) is equal to the name.
+    Name(String),
+    /// Modules for which the predicate returns `true`.
+    Predicate(Box<dyn Fn(&ModuleInfo) -> bool + Send + Sync>),
+}
+
+impl ModuleFilter {
+    /// Creates a filter from a predicate.
+    pub fn predicate<F>(f: F) -> Self
+    where
+        F: Fn(&ModuleInfo) -> bool + Send + Sync + 'static,
+    {
+        Self::Predicate(Box::new(f))
+    }
+
+    /// Returns `true` if the module is selected by this filter.
+    pub fn matches(&self, module: &ModuleInfo) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Name(name) => module.matches(name),
+            Self::Predicate(predicate) => predicate(module),
+        }
+    }
+}
+
+impl From<&str> for ModuleFilter {
+    fn from(value: &str) -> Self {
+        Self::Name(value.to_owned())
+    }
+}
+
+impl From<String> for ModuleFilter {
+    fn from(value: String) -> Self {
+        Self::Name(value)
+    }
+}
+
+/// Identifies a registered callback, see [`remove`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WatchId(u64);
+
+type LoadedCallback = Box<dyn FnMut(&ModuleInfo, Option<&Library>) + Send>;
+type UnloadedCallback = Box<dyn FnMut(&ModuleInfo) + Send>;
+
+enum Callback {
+    Loaded(LoadedCallback),
+    Unloaded(UnloadedCallback),
+}
+
+struct Watcher {
+    id: WatchId,
+    filter: ModuleFilter,
+    callback: Mutex<Callback>,
+}
+
+#[derive(Default)]
+struct Snapshot {
+    counters: Option<(u64, u64)>,
+    modules: Option<Vec<ModuleInfo>>,
+}
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+static WATCHERS: Mutex<Vec<Arc<Watcher>>> = Mutex::new(Vec::new());
+static SNAPSHOT: Mutex<Snapshot> = Mutex::new(Snapshot {
+    counters: None,
+    modules: None,
+});
+static DISPATCH: Mutex<()> = Mutex::new(());
+
+thread_local! {
+    static DISPATCHING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Registers a callback for modules matching `filter` being loaded.
+///
+/// The callback is invoked right away for matching modules that are already loaded, and later
+/// from [`poll`] for every new one. Besides the module information it receives the module opened
+/// with `xdl_open2`, or `None` if the module was unloaded again before it could be opened.
+pub fn on_module_loaded<F, C>(filter: F, callback: C) -> WatchId
+where
+    F: Into<ModuleFilter>,
+    C: FnMut(&ModuleInfo, Option<&Library>) + Send + 'static,
+{
+    let (watcher, modules) = register(filter.into(), Callback::Loaded(Box::new(callback)));
+    for module in &modules {
+        notify(&watcher, module, &mut LazyOpen::new(module));
+    }
+    watcher.id
+}
+
+/// Registers a callback for modules matching `filter` being unloaded.
+///
+/// The module is already gone when the callback runs, so only its information is passed.
+pub fn on_module_unloaded<F, C>(filter: F, callback: C) -> WatchId
+where
+    F: Into<ModuleFilter>,
+    C: FnMut(&ModuleInfo) + Send + 'static,
+{
+    let (watcher, _) = register(filter.into(), Callback::Unloaded(Box::new(callback)));
+    watcher.id
+}
+
+/// Unregisters a callback. Returns `false` if no callback with this id was registered.
+///
+/// A poll running concurrently on another thread may still invoke the callback once.
+pub fn remove(id: WatchId) -> bool {
+    let mut watchers = lock(&WATCHERS);
+    let len = watchers.len();
+    watchers.retain(|watcher| watcher.id != id);
+    watchers.len() != len
+}
+
+/// Compares the loaded modules against the last snapshot and invokes the registered callbacks
+/// for every change.
+///
+/// Calling `poll` from inside a callback does nothing.
+pub fn poll() {
+    if DISPATCHING.get() {
+        return;
+    }
+    let _dispatch = lock(&DISPATCH);
+    let (loaded, unloaded, watchers) = {
+        let mut snapshot = lock(&SNAPSHOT);
+        let counters = crate::module::load_counters();
+        if snapshot.modules.is_some() && counters.is_some() && counters == snapshot.counters {
+            return;
+        }
+        let current = crate::module::loaded_modules();
+        let previous = snapshot.modules.replace(current.clone());
+        let changes =
+            counters
+                .zip(snapshot.counters)
+                .map(|((adds, subs), (old_adds, old_subs))| {
+                    (adds.wrapping_sub(old_adds), subs.wrapping_sub(old_subs))
+                });
+        snapshot.counters = counters;
+        let Some(previous) = previous else {
+            // First snapshot: no watcher has been registered yet, nothing to report.
+            return;
+        };
+        let (loaded, unloaded) = diff(&previous, current, changes);
+        // The watcher list is taken together with the snapshot, so a watcher registered
+        // after this point is not notified twice about the same module.
+        (loaded, unloaded, lock(&WATCHERS).clone())
+    };
+    if loaded.is_empty() && unloaded.is_empty() {
+        return;
+    }
+
+    let _dispatching = Dispatching::enter();
+    for module in &unloaded {
+        for watcher in &watchers {
+            notify(watcher, module, &mut LazyOpen::gone());
+        }
+    }
+    for module in &loaded {
+        let mut lib = LazyOpen::new(module);
+        for watcher in &watchers {
+            notify(watcher, module, &mut lib);
+        }
+    }
+}
+
+/// Marks the current thread as invoking callbacks, until dropped (even by a panicking
+/// callback).
+struct Dispatching;
+
+impl Dispatching {
+    fn enter() -> Self {
+        DISPATCHING.set(true);
+        Self
+    }
+}
+
+impl Drop for Dispatching {
+    fn drop(&mut self) {
+        DISPATCHING.set(false);
+    }
+}
+
+/// Returns `true` if the current thread is running a callback from [`poll`].
+pub(crate) fn is_dispatching() -> bool {
+    DISPATCHING.get()
+}
+
+/// Background thread calling [`poll`] periodically, see [`spawn_poller`].
+///
+/// The thread is stopped when the `Poller` is dropped. Dropped from one of its own callbacks, it
+/// is not waited for: the thread exits once the current poll returns.
+#[derive(Debug)]
+pub struct Poller {
+    stop: Arc<(Mutex<bool>, Condvar)>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Poller {
+    /// Stops the polling thread and waits for it to exit, unless called from the polling thread
+    /// itself.
+    pub fn stop(self) {}
+}
+
+impl Drop for Poller {
+    fn drop(&mut self) {
+        let (stopped, condvar) = &*self.stop;
+        *lock(stopped) = true;
+        condvar.notify_all();
+        if let Some(thread) = self.thread.take()
+            && thread.thread().id() != std::thread::current().id()
+        {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Spawns a background thread that calls [`poll`] every `interval`.
+pub fn spawn_poller(interval: Duration) -> Poller {
+    let stop = Arc::new((Mutex::new(false), Condvar::new()));
+    let thread = {
+        let stop = stop.clone();
+        std::thread::Builder::new()
+            .name("xdl-watch".to_string())
+            .spawn(move || {
+                let (stopped, condvar) = &*stop;
+                let mut guard = lock(stopped);
+                while !*guard {
+                    drop(guard);
+                    poll();
+                    guard = lock(stopped);
+                    if !*guard {
+                        guard = condvar
+                            .wait_timeout(guard, interval)
+                            .unwrap_or_else(PoisonError::into_inner)
+                            .0;
+                    }
+                }
+            })
+            .expect("failed to spawn module watcher thread")
+    };
+    Poller {
+        stop,
+        thread: Some(thread),
+    }
+}
+
+fn register(filter: ModuleFilter, callback: Callback) -> (Arc<Watcher>, Vec<ModuleInfo>) {
+    let watcher = Arc::new(Watcher {
+        id: WatchId(NEXT_ID.fetch_add(1, Ordering::Relaxed)),
+        filter,
+        callback: Mutex::new(callback),
+    });
+    let mut snapshot = lock(&SNAPSHOT);
+    if snapshot.modules.is_none() {
+        snapshot.counters = crate::module::load_counters();
+        snapshot.modules = Some(crate::module::loaded_modules());
+    }
+    lock(&WATCHERS).push(watcher.clone());
+    let modules = snapshot.modules.clone().unwrap_or_default();
+    (watcher, modules)
+}
+
+fn notify(watcher: &Watcher, module: &ModuleInfo, lib: &mut LazyOpen) {
+    if !watcher.filter.matches(module) {
+        return;
+    }
+    match &mut *lock(&watcher.callback) {
+        Callback::Loaded(callback) => callback(module, lib.get()),
+        Callback::Unloaded(callback) => callback(module),
+    }
+}
+
+/// Compares two snapshots, both in load order, into the modules loaded and unloaded in between.
+///
+/// Modules are identified by their load bias and program headers, which stay the same when a
+/// module is unloaded and loaded again at the same address. As the linker appends the modules it
+/// loads to its list, such a module is still recognized when it now comes after a module that was
+/// loaded after it. Otherwise it stays at the end of the list: if `changes`, the number of loads
+/// and unloads the linker counted in between, is larger than the changes found, that many modules
+/// at the end of the list are reported as reloaded. A module loaded and unloaded in between is
+/// counted as well, so this may report a module that was not reloaded.
+fn diff(
+    previous: &[ModuleInfo],
+    current: Vec<ModuleInfo>,
+    changes: Option<(u64, u64)>,
+) -> (Vec<ModuleInfo>, Vec<ModuleInfo>) {
+    let key = |module: &ModuleInfo| (module.base(), module.phdr_address());
+    let order = previous
+        .iter()
+        .enumerate()
+        .map(|(index, module)| (key(module), index))
+        .collect::<HashMap<_, _>>();
+    // The modules kept from `previous` come first, in their previous order.
+    let mut last = None;
+    let mut kept = current
+        .iter()
+        .map(|module| match order.get(&key(module)) {
+            Some(&index) if last.is_none_or(|last| index > last) => {
+                last = Some(index);
+                true
+            }
+            _ => false,
+        })
+        .collect::<Vec<_>>();
+    if let Some((adds, subs)) = changes {
+        let loaded = kept.iter().filter(|&&kept| !kept).count() as u64;
+        let unloaded = (previous.len() - kept.iter().filter(|&&kept| kept).count()) as u64;
+        let reloaded = adds
+            .saturating_sub(loaded)
+            .min(subs.saturating_sub(unloaded));
+        kept.iter_mut()
+            .rev()
+            .filter(|kept| **kept)
+            .take(reloaded as usize)
+            .for_each(|kept| *kept = false);
+    }
+    let kept_keys = current
+        .iter()
+        .zip(&kept)
+        .filter(|(_, kept)| **kept)
+        .map(|(module, _)| key(module))
+        .collect::<HashSet<_>>();
+    let unloaded = previous
+        .iter()
+        .filter(|module| !kept_keys.contains(&key(module)))
+        .cloned()
+        .collect();
+    let loaded = current
+        .into_iter()
+        .zip(kept)
+        .filter_map(|(module, kept)| (!kept).then_some(module))
+        .collect();
+    (loaded, unloaded)
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Opens a module at most once, and only if some callback actually needs it.
+enum LazyOpen<'a> {
+    Pending(&'a ModuleInfo),
+    Opened(Option<Library>),
+}
+
+impl<'a> LazyOpen<'a> {
+    fn new(module: &'a ModuleInfo) -> Self {
+        Self::Pending(module)
+    }
+
+    fn gone() -> Self {
+        Self::Opened(None)
+    }
+
+    fn get(&mut self) -> Option<&Library> {
+        if let Self::Pending(module) = self {
+            *self = Self::Opened(module.open().ok());
+        }
+        match self {
+            Self::Opened(lib) => lib.as_ref(),
+            Self::Pending(_) => unreachable!(),
+        }
+    }
+}
+
+/// Poll interval of the poller shared by everything waiting on [`shared_poller`].
+const SHARED_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+static SHARED_POLLER: Mutex<(usize, Option<Poller>)> = Mutex::new((0, None));
+
+/// Keeps the shared background poller alive, see [`shared_poller`].
+pub(crate) struct SharedPoller(());
+
+impl Drop for SharedPoller {
+    fn drop(&mut self) {
+        let poller = {
+            let mut shared = lock(&SHARED_POLLER);
+            shared.0 -= 1;
+            if shared.0 == 0 { shared.1.take() } else { None }
+        };
+        // Joined outside the lock, the thread may be in the middle of a poll.
+        drop(poller);
+    }
+}
+
+/// Starts (or joins) a background poller shared by all internal waiters.
+///
+/// The thread runs for as long as at least one [`SharedPoller`] is alive.
+pub(crate) fn shared_poller() -> SharedPoller {
+    let mut shared = lock(&SHARED_POLLER);
+    shared.0 += 1;
+    if shared.1.is_none() {
+        shared.1 = Some(spawn_poller(SHARED_POLL_INTERVAL));
+    }
+    SharedPoller(())
+}
+
+/// Unregisters a callback when dropped.
+pub(crate) struct WatchGuard(pub(crate) WatchId);
+
+impl Drop for WatchGuard {
+    fn drop(&mut self) {
+        remove(self.0);
+    }
+}
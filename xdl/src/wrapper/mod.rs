@@ -88,9 +88,18 @@ This trait is intended to be used with `#[derive(NativeBridge)]` macro defined i
 * You can't define a type using `type Fun =fn();` and use it in the structure. This is a limitation
   of the Rust reflection mechanism. Only raw functions, references and pointers are allowed.
 
+The generated `load_from()` resolves all symbols of the structure with one call to
+[`Library::resolve_batch`], which is much faster than one lookup per field for large APIs.
+The hashes of the symbol names are computed at compile time, see
+[`SymbolHash`](crate::SymbolHash).
+
 The `derive` macro not only generates implementation of `load_from()` function, but it also generates
 safe wrappers around the loaded symbols. These wrappers are named exactly like the field that
 they wrap.
+For references to atomic types, such as `&'a AtomicU32`, `load_<field>(order)` and
+`store_<field>(value, order)` are generated as well, for global variables that other threads
+modify concurrently. `load_from()` fails if a `&mut T` or atomic field is not in a writable
+segment, see [`Library::check_writable`].
 Wrappers are not generated only for:
 * Pointers - there is no safe way of preventing dangling symbols if a user has a direct access to
   pointers. The recommended approach here is to either use references instead of pointers or
@@ -143,6 +152,13 @@ a standalone object. API and library handle need to be kept together to prevent
 **Note:** By default obtained symbol name is the field name. You can change this by
 assigning the `#[native(symbol = "...")]` attribute to the given field.
 
+**Note:** With `#[native(checked)]` on the structure, the `.dynsym` entry of each symbol is
+checked against the type of its field before binding it, see [`SymbolKind`](crate::SymbolKind).
+
+**Note:** A field declared as [`LazySymbol<'a, T>`](crate::LazySymbol) with the
+`#[native(lazy)]` attribute is not resolved by `load_from()`, but on its first use. Its wrapper
+returns `Result`, with the error of the lookup if the symbol can not be found.
+
 **Note:** By default `Error::SymbolNotFound` is returned if the loaded symbol name has a null value.
 While null is a valid value of a exported symbol, it is usually not expected by users of libraries.
 If a `null` value is acceptable for a pointer field in your scenario,
@@ -0,0 +1,45 @@
+#!/bin/sh
+# Regenerates the fixtures used by the tests in `tests/`.
+#
+# Needs gcc with 32-bit support, an `ld.lld` (e.g. the `rust-lld` shipped with rustup, run as
+# `LLD="rust-lld -flavor gnu" ./build.sh`) and an `llvm-mc` with the ARM and AArch64 targets.
+set -e
+cd "$(dirname "$0")"
+LLD=${LLD:-ld.lld}
+MC=${MC:-llvm-mc}
+tmp=$(mktemp -d)
+trap 'rm -rf "$tmp"' EXIT
+
+gcc -c -fPIC -O2 -o "$tmp/reloc64.o" reloc.c
+gcc -c -fPIC -O2 -m32 -o "$tmp/reloc32.o" reloc.c
+
+$LLD -shared --hash-style=both -soname librela.so -o librela.so "$tmp/reloc64.o"
+$LLD -shared --hash-style=both -m elf_i386 -soname librel.so -o librel.so "$tmp/reloc32.o"
+$LLD -shared --hash-style=both -z pack-relative-relocs -soname librelr.so -o librelr.so "$tmp/reloc64.o"
+$LLD -shared --hash-style=both --pack-dyn-relocs=android -soname libaps2.so -o libaps2.so "$tmp/reloc64.o"
+$LLD -shared --hash-style=both -m elf_i386 --pack-dyn-relocs=android -soname libaps2_32.so \
+    -o libaps2_32.so "$tmp/reloc32.o"
+
+# `reloc.c` written in assembly for ARM and AArch64, see `reloc_arm.s` and `reloc_arm64.s`.
+$MC -filetype=obj --triple=aarch64-linux-android -o "$tmp/reloc_arm64.o" reloc_arm64.s
+$MC -filetype=obj --triple=armv7a-linux-androideabi -o "$tmp/reloc_arm.o" reloc_arm.s
+
+$LLD -shared --hash-style=both -soname librela_arm64.so -o librela_arm64.so "$tmp/reloc_arm64.o"
+$LLD -shared --hash-style=both -z pack-relative-relocs -soname librelr_arm64.so \
+    -o librelr_arm64.so "$tmp/reloc_arm64.o"
+$LLD -shared --hash-style=both --pack-dyn-relocs=android -soname libaps2_arm64.so \
+    -o libaps2_arm64.so "$tmp/reloc_arm64.o"
+$LLD -shared --hash-style=both -soname librel_arm.so -o librel_arm.so "$tmp/reloc_arm.o"
+$LLD -shared --hash-style=both -z pack-relative-relocs -soname librelr_arm.so \
+    -o librelr_arm.so "$tmp/reloc_arm.o"
+$LLD -shared --hash-style=both --pack-dyn-relocs=android -soname libaps2_arm.so \
+    -o libaps2_arm.so "$tmp/reloc_arm.o"
+
+# Reference build for `tests/offsets.rs`, identified by its build ID.
+$LLD -shared --hash-style=both --build-id=sha1 -soname libbuildid.so -o libbuildid.so "$tmp/reloc64.o"
+
+# MiniDebugInfo: `.symtab` moved to an xz-compressed ELF file in `.gnu_debugdata`.
+objcopy --only-keep-debug librela.so "$tmp/debuginfo"
+strip --strip-all -o libdebugdata.so librela.so
+xz --stdout "$tmp/debuginfo" > "$tmp/debuginfo.xz"
+objcopy --add-section .gnu_debugdata="$tmp/debuginfo.xz" libdebugdata.so
@@ -0,0 +1,10 @@
+extern int ext_var;
+extern int ext_fn(int);
+static int local_var = 1;
+int global_var = 2;
+int *ptrs[16] = {
+    &local_var, &local_var, &local_var, &local_var, &local_var, &local_var, &local_var, &local_var,
+    &local_var, &local_var, &local_var, &local_var, &local_var, &local_var, &global_var, &ext_var,
+};
+int (*fptr)(int) = ext_fn;
+int call(int x) { return ext_fn(x) + ext_var + *ptrs[1]; }
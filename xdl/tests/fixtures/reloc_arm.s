@@ -0,0 +1,74 @@
+@ `reloc.c` for ARM, plus `ifunc_fn`, an indirect function called through the PLT.
+    .syntax unified
+    .arm
+    .text
+    .globl call
+    .p2align 2
+    .type call, %function
+call:
+    push {r4, lr}
+    bl ext_fn
+    bl ifunc_fn
+    ldr r1, .Lext_var
+.Lpc0:
+    ldr r1, [pc, r1]
+    ldr r1, [r1]
+    ldr r2, .Lptrs
+.Lpc1:
+    ldr r2, [pc, r2]
+    ldr r2, [r2, #4]
+    ldr r2, [r2]
+    add r0, r0, r1
+    add r0, r0, r2
+    pop {r4, pc}
+    .p2align 2
+.Lext_var:
+    .long ext_var(GOT_PREL)-((.Lpc0+8)-.Lext_var)
+.Lptrs:
+    .long ptrs(GOT_PREL)-((.Lpc1+8)-.Lptrs)
+    .size call, .-call
+
+    .p2align 2
+    .type ifunc_impl, %function
+ifunc_impl:
+    bx lr
+    .size ifunc_impl, .-ifunc_impl
+
+    .globl ifunc_fn
+    .hidden ifunc_fn
+    .p2align 2
+    .type ifunc_fn, %gnu_indirect_function
+ifunc_fn:
+    adr r0, ifunc_impl
+    bx lr
+    .size ifunc_fn, .-ifunc_fn
+
+    .data
+    .p2align 2
+    .type local_var, %object
+local_var:
+    .long 1
+    .size local_var, 4
+
+    .globl global_var
+    .type global_var, %object
+global_var:
+    .long 2
+    .size global_var, 4
+
+    .globl ptrs
+    .type ptrs, %object
+ptrs:
+    .rept 14
+    .long local_var
+    .endr
+    .long global_var
+    .long ext_var
+    .size ptrs, 64
+
+    .globl fptr
+    .type fptr, %object
+fptr:
+    .long ext_fn
+    .size fptr, 4
+
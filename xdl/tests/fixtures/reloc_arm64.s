@@ -0,0 +1,65 @@
+// `reloc.c` for AArch64, plus `ifunc_fn`, an indirect function called through the PLT.
+    .text
+    .globl call
+    .type call, %function
+call:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    bl ext_fn
+    bl ifunc_fn
+    adrp x8, :got:ext_var
+    ldr x8, [x8, :got_lo12:ext_var]
+    ldr w8, [x8]
+    adrp x9, :got:ptrs
+    ldr x9, [x9, :got_lo12:ptrs]
+    ldr x9, [x9, #8]
+    ldr w9, [x9]
+    add w0, w0, w8
+    add w0, w0, w9
+    ldp x29, x30, [sp], #16
+    ret
+    .size call, .-call
+
+    .type ifunc_impl, %function
+ifunc_impl:
+    ret
+    .size ifunc_impl, .-ifunc_impl
+
+    .globl ifunc_fn
+    .hidden ifunc_fn
+    .type ifunc_fn, %gnu_indirect_function
+ifunc_fn:
+    adr x0, ifunc_impl
+    ret
+    .size ifunc_fn, .-ifunc_fn
+
+    .data
+    .p2align 2
+    .type local_var, %object
+local_var:
+    .word 1
+    .size local_var, 4
+
+    .globl global_var
+    .type global_var, %object
+global_var:
+    .word 2
+    .size global_var, 4
+
+    .globl ptrs
+    .p2align 3
+    .type ptrs, %object
+ptrs:
+    .rept 14
+    .xword local_var
+    .endr
+    .xword global_var
+    .xword ext_var
+    .size ptrs, 128
+
+    .globl fptr
+    .type fptr, %object
+fptr:
+    .xword ext_fn
+    .size fptr, 8
+
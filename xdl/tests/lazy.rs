@@ -0,0 +1,43 @@
+//! Binds the same symbols eagerly and lazily through `#[derive(NativeBridge)]`.
+//!
+//! Runs on a device or emulator only, e.g. with `cargo ndk test`.
+#![cfg(target_os = "android")]
+
+use android_xdl::derive::NativeBridge;
+use android_xdl::wrapper::Container;
+use android_xdl::{LazySymbol, Library};
+use std::os::raw::{c_char, c_void};
+
+/// `strlen` and `memcpy` are indirect functions (`STT_GNU_IFUNC`) in recent arm64 builds of
+/// bionic, and plain functions elsewhere.
+#[derive(NativeBridge)]
+struct Libc<'a> {
+    strlen: unsafe extern "C" fn(s: *const c_char) -> usize,
+    memcpy: unsafe extern "C" fn(dst: *mut c_void, src: *const c_void, n: usize) -> *mut c_void,
+    #[native(lazy, symbol = ["strlen"])]
+    lazy_strlen: LazySymbol<'a, unsafe extern "C" fn(s: *const c_char) -> usize>,
+    #[native(lazy, symbol = ["memcpy"])]
+    lazy_memcpy: LazySymbol<
+        'a,
+        unsafe extern "C" fn(dst: *mut c_void, src: *const c_void, n: usize) -> *mut c_void,
+    >,
+}
+
+/// The address `dlsym` returns, after running the resolver of an indirect function.
+fn dlsym(name: &std::ffi::CStr) -> usize {
+    unsafe { libc::dlsym(libc::RTLD_DEFAULT, name.as_ptr()) as usize }
+}
+
+#[test]
+fn eager_and_lazy_fields_bind_the_same_address() {
+    let api: Container<Libc> = Container::from(Library::open(c"libc.so").unwrap()).unwrap();
+
+    let strlen = api.lazy_strlen.get().unwrap();
+    assert_eq!(strlen as usize, api.strlen as usize);
+    assert_eq!(strlen as usize, dlsym(c"strlen"));
+    assert_eq!(unsafe { strlen(c"lazy".as_ptr()) }, 4);
+
+    let memcpy = api.lazy_memcpy.get().unwrap();
+    assert_eq!(memcpy as usize, api.memcpy as usize);
+    assert_eq!(memcpy as usize, dlsym(c"memcpy"));
+}
@@ -0,0 +1,216 @@
+//! Parses, serializes and queries offset databases, and generates entries from
+//! `fixtures/libbuildid.so` (see `build.sh`).
+
+use android_xdl::Error;
+use android_xdl::elf::{BuildId, ElfFile};
+use android_xdl::offsets::{Arch, OffsetDatabase, OffsetEntry};
+use std::collections::BTreeMap;
+
+const BUILD_ID: &str = "5f2c6b3d0e8f4a51b5b2c8d9e6f7a1b2c3d4e5f6";
+const REFERENCE: &[u8] = include_bytes!("fixtures/libbuildid.so");
+const NO_BUILD_ID: &[u8] = include_bytes!("fixtures/librela.so");
+
+const TOML: &str = r#"
+[[library]]
+name = "libgame.so"
+build_id = "5f2c6b3d0e8f4a51b5b2c8d9e6f7a1b2c3d4e5f6"
+arch = "arm64-v8a"
+
+[library.offsets]
+update_player = 0x1a2b30
+damage_table = "0x3c0010"
+"#;
+
+const JSON: &str = r#"{
+    "library": [{
+        "name": "libgame.so",
+        "build_id": "5f2c6b3d0e8f4a51b5b2c8d9e6f7a1b2c3d4e5f6",
+        "arch": "aarch64",
+        "offsets": {"update_player": 1714992, "damage_table": "3c0010"}
+    }]
+}"#;
+
+fn build_id() -> BuildId {
+    BUILD_ID.parse().unwrap()
+}
+
+fn entry(name: &str, arch: Arch, offsets: &[(&str, u64)]) -> OffsetEntry {
+    OffsetEntry {
+        name: name.to_string(),
+        build_id: build_id(),
+        arch,
+        offsets: offsets
+            .iter()
+            .map(|(name, offset)| (name.to_string(), *offset))
+            .collect(),
+    }
+}
+
+fn expected() -> OffsetDatabase {
+    let mut db = OffsetDatabase::new();
+    db.insert(entry(
+        "libgame.so",
+        Arch::Arm64,
+        &[("update_player", 0x1a2b30), ("damage_table", 0x3c0010)],
+    ));
+    db
+}
+
+#[test]
+fn toml() {
+    assert_eq!(OffsetDatabase::from_toml(TOML).unwrap(), expected());
+}
+
+#[test]
+fn json() {
+    assert_eq!(OffsetDatabase::from_json(JSON).unwrap(), expected());
+}
+
+#[test]
+fn round_trip() {
+    let db = expected();
+    assert_eq!(
+        OffsetDatabase::from_toml(&db.to_toml().unwrap()).unwrap(),
+        db
+    );
+    assert_eq!(
+        OffsetDatabase::from_json(&db.to_json().unwrap()).unwrap(),
+        db
+    );
+    assert!(db.to_toml().unwrap().contains(BUILD_ID));
+}
+
+#[test]
+fn empty() {
+    assert_eq!(
+        OffsetDatabase::from_toml("").unwrap(),
+        OffsetDatabase::new()
+    );
+    assert_eq!(
+        OffsetDatabase::from_json("{}").unwrap(),
+        OffsetDatabase::new()
+    );
+}
+
+#[test]
+fn invalid() {
+    let offset = TOML.replace("\"0x3c0010\"", "\"0xnope\"");
+    let build_id = TOML.replace(BUILD_ID, "not hex");
+    let arch = TOML.replace("arm64-v8a", "mips");
+    for text in [offset, build_id, arch] {
+        assert!(matches!(
+            OffsetDatabase::from_toml(&text),
+            Err(Error::OffsetDatabaseError(_))
+        ));
+    }
+    assert!(matches!(
+        OffsetDatabase::from_json("{\"library\": 1}"),
+        Err(Error::OffsetDatabaseError(_))
+    ));
+}
+
+#[test]
+fn insert_merges_same_build() {
+    let mut db = OffsetDatabase::new();
+    db.insert(entry("libgame.so", Arch::Arm64, &[("a", 1), ("b", 2)]));
+    db.insert(entry("libgame.so", Arch::Arm64, &[("b", 3), ("c", 4)]));
+    db.insert(entry("libgame.so", Arch::Arm, &[("a", 5)]));
+    assert_eq!(db.entries().len(), 2);
+    let offsets = &db.entries()[0].offsets;
+    let merged = BTreeMap::from([
+        ("a".to_string(), 1),
+        ("b".to_string(), 3),
+        ("c".to_string(), 4),
+    ]);
+    assert_eq!(*offsets, merged);
+    assert_eq!(db.entries()[1].offset("a"), Some(5));
+}
+
+#[test]
+fn find() {
+    let mut db = expected();
+    db.insert(entry("/vendor/lib64/libfull.so", Arch::X86_64, &[]));
+    let id = build_id();
+
+    let basename = db.find("/data/app/lib/arm64/libgame.so", &id, Arch::Arm64);
+    assert_eq!(basename.unwrap().offset("update_player"), Some(0x1a2b30));
+    assert!(db.find("libgame.so", &id, Arch::Arm64).is_some());
+    assert!(
+        db.find("/data/app/lib/arm64/libgame.so.1", &id, Arch::Arm64)
+            .is_none()
+    );
+    assert!(db.find("libgame.so", &id, Arch::Arm).is_none());
+    assert!(
+        db.find("libgame.so", &BuildId::new(vec![1, 2]), Arch::Arm64)
+            .is_none()
+    );
+
+    // An entry named with a full path only matches that path.
+    assert!(
+        db.find("/vendor/lib64/libfull.so", &id, Arch::X86_64)
+            .is_some()
+    );
+    assert!(
+        db.find("/system/lib64/libfull.so", &id, Arch::X86_64)
+            .is_none()
+    );
+    assert!(db.find("libfull.so", &id, Arch::X86_64).is_none());
+}
+
+#[test]
+fn load_by_extension() {
+    let dir = std::env::temp_dir().join(format!("xdl-offsets-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let toml = dir.join("offsets.toml");
+    let json = dir.join("offsets.JSON");
+    std::fs::write(&toml, TOML).unwrap();
+    std::fs::write(&json, JSON).unwrap();
+    let loaded = (OffsetDatabase::load(&toml), OffsetDatabase::load(&json));
+    let missing = OffsetDatabase::load(dir.join("missing.toml"));
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(loaded.0.unwrap(), expected());
+    assert_eq!(loaded.1.unwrap(), expected());
+    assert!(matches!(missing, Err(Error::OffsetDatabaseError(_))));
+}
+
+#[test]
+fn from_reference() {
+    let file = ElfFile::parse(REFERENCE.to_vec()).unwrap();
+    let entry = OffsetEntry::from_reference(&file, None, ["call", "ptrs"]).unwrap();
+    assert_eq!(entry.name, "libbuildid.so");
+    assert_eq!(entry.build_id, file.build_id().unwrap());
+    assert_eq!(entry.arch, Arch::X86_64);
+    assert_eq!(
+        entry.offset("call"),
+        Some(file.find_symbol("call").unwrap().value)
+    );
+    assert_eq!(
+        entry.offset("ptrs"),
+        Some(file.find_symbol("ptrs").unwrap().value)
+    );
+    assert_eq!(entry.offsets.len(), 2);
+
+    let renamed = OffsetEntry::from_reference(&file, Some("libgame.so"), ["call"]).unwrap();
+    assert_eq!(renamed.name, "libgame.so");
+
+    assert!(matches!(
+        OffsetEntry::from_reference(&file, None, ["missing"]),
+        Err(Error::SymbolNotFound(_))
+    ));
+    let without_id = ElfFile::parse(NO_BUILD_ID.to_vec()).unwrap();
+    assert!(matches!(
+        OffsetEntry::from_reference(&without_id, None, ["call"]),
+        Err(Error::InvalidElf(_))
+    ));
+}
+
+#[test]
+fn arch() {
+    assert_eq!(Arch::from_machine(183), Some(Arch::Arm64));
+    assert_eq!(Arch::from_machine(40), Some(Arch::Arm));
+    assert_eq!(Arch::from_machine(3), Some(Arch::X86));
+    assert_eq!(Arch::from_machine(62), Some(Arch::X86_64));
+    assert_eq!(Arch::from_machine(8), None);
+    assert_eq!(Arch::Arm64.to_string(), "arm64");
+}
@@ -0,0 +1,195 @@
+//! Decodes the relocation tables of the modules in `tests/fixtures`, which are all linked from
+//! `reloc.c` (or its ARM and AArch64 versions in assembly) and differ only in their machine and
+//! the format of their tables (see `build.sh`).
+
+use android_xdl::Error;
+use android_xdl::elf::{ElfFile, Relocation, RelocationKind, RelocationTable};
+
+const RELA: &[u8] = include_bytes!("fixtures/librela.so");
+const REL: &[u8] = include_bytes!("fixtures/librel.so");
+const RELR: &[u8] = include_bytes!("fixtures/librelr.so");
+const APS2: &[u8] = include_bytes!("fixtures/libaps2.so");
+const APS2_32: &[u8] = include_bytes!("fixtures/libaps2_32.so");
+const RELA_ARM64: &[u8] = include_bytes!("fixtures/librela_arm64.so");
+const RELR_ARM64: &[u8] = include_bytes!("fixtures/librelr_arm64.so");
+const APS2_ARM64: &[u8] = include_bytes!("fixtures/libaps2_arm64.so");
+const REL_ARM: &[u8] = include_bytes!("fixtures/librel_arm.so");
+const RELR_ARM: &[u8] = include_bytes!("fixtures/librelr_arm.so");
+const APS2_ARM: &[u8] = include_bytes!("fixtures/libaps2_arm.so");
+
+/// Virtual address of the `APS2` table of `libaps2.so` (`DT_ANDROID_RELA`).
+const APS2_TABLE: usize = 0x350;
+/// Virtual address of the `RELR` table of `librelr.so` (`DT_RELR`).
+const RELR_TABLE: usize = 0x3c8;
+
+fn relocations(data: &[u8]) -> (ElfFile, Vec<Relocation>) {
+    let file = ElfFile::parse(data.to_vec()).unwrap();
+    let relocations = file.relocations().unwrap().collect();
+    (file, relocations)
+}
+
+fn symbolic(relocations: &[Relocation], kind: RelocationKind) -> Vec<&str> {
+    let mut names: Vec<_> = relocations
+        .iter()
+        .filter(|relocation| relocation.kind == kind)
+        .map(|relocation| relocation.symbol.as_ref().unwrap().name.as_str())
+        .collect();
+    names.sort_unstable();
+    names
+}
+
+/// Checks the relocations that every fixture has, whatever the format of its tables, and the
+/// `irelative` relocations of the indirect functions it calls.
+fn check(data: &[u8], relative_table: RelocationTable, explicit_addend: bool, irelative: usize) {
+    let (file, relocations) = relocations(data);
+    let word = if file.is_64bit() { 8 } else { 4 };
+    let ptrs = file.find_symbol("ptrs").unwrap().value;
+
+    // The first 14 elements of `ptrs` point to `local_var`.
+    let relative: Vec<_> = relocations
+        .iter()
+        .filter(|relocation| relocation.kind == RelocationKind::Relative)
+        .collect();
+    let mut offsets: Vec<_> = relative
+        .iter()
+        .map(|relocation| relocation.offset)
+        .collect();
+    offsets.sort_unstable();
+    let expected: Vec<_> = (0..14).map(|i| ptrs + i * word).collect();
+    assert_eq!(offsets, expected);
+    for relocation in &relative {
+        assert_eq!(relocation.table, relative_table);
+        assert!(relocation.symbol.is_none());
+        assert_eq!(relocation.addend.is_some(), explicit_addend);
+        assert_eq!(relocation.addend, relative[0].addend);
+    }
+    if explicit_addend {
+        assert_ne!(relative[0].addend, Some(0));
+    }
+
+    assert_eq!(
+        symbolic(&relocations, RelocationKind::Absolute),
+        ["ext_fn", "ext_var", "global_var"]
+    );
+    assert_eq!(
+        symbolic(&relocations, RelocationKind::GlobDat),
+        ["ext_var", "ptrs"]
+    );
+    assert_eq!(symbolic(&relocations, RelocationKind::JumpSlot), ["ext_fn"]);
+    let indirect = relocations
+        .iter()
+        .filter(|relocation| relocation.kind == RelocationKind::IRelative)
+        .inspect(|relocation| assert!(relocation.symbol.is_none()))
+        .count();
+    assert_eq!(indirect, irelative);
+    assert_eq!(relocations.len(), 14 + 6 + irelative);
+
+    let absolute = |name: &str| {
+        relocations
+            .iter()
+            .find(|relocation| {
+                relocation.kind == RelocationKind::Absolute
+                    && relocation.symbol.as_ref().unwrap().name == name
+            })
+            .unwrap()
+    };
+    assert_eq!(absolute("global_var").offset, ptrs + 14 * word);
+    assert_eq!(absolute("ext_var").offset, ptrs + 15 * word);
+    assert!(absolute("ext_var").symbol.as_ref().unwrap().undefined);
+    assert!(!absolute("global_var").symbol.as_ref().unwrap().undefined);
+}
+
+#[test]
+fn rela() {
+    check(RELA, RelocationTable::Rela, true, 0);
+}
+
+#[test]
+fn rel() {
+    check(REL, RelocationTable::Rel, false, 0);
+}
+
+#[test]
+fn relr() {
+    check(RELR, RelocationTable::Relr, false, 0);
+}
+
+#[test]
+fn aps2() {
+    check(APS2, RelocationTable::AndroidRela, true, 0);
+}
+
+#[test]
+fn aps2_32() {
+    check(APS2_32, RelocationTable::AndroidRel, false, 0);
+}
+
+#[test]
+fn rela_arm64() {
+    check(RELA_ARM64, RelocationTable::Rela, true, 1);
+}
+
+#[test]
+fn relr_arm64() {
+    check(RELR_ARM64, RelocationTable::Relr, false, 1);
+}
+
+#[test]
+fn aps2_arm64() {
+    check(APS2_ARM64, RelocationTable::AndroidRela, true, 1);
+}
+
+#[test]
+fn rel_arm() {
+    check(REL_ARM, RelocationTable::Rel, false, 1);
+}
+
+#[test]
+fn relr_arm() {
+    check(RELR_ARM, RelocationTable::Relr, false, 1);
+}
+
+#[test]
+fn aps2_arm() {
+    check(APS2_ARM, RelocationTable::AndroidRel, false, 1);
+}
+
+#[test]
+fn aps2_group_larger_than_count() {
+    let mut data = APS2.to_vec();
+    // "APS2", count, offset, then the size of the first group, which has a single entry.
+    assert_eq!(data[APS2_TABLE..APS2_TABLE + 7], *b"APS2\x13\x00\x01");
+    data[APS2_TABLE + 6] = 0x3f;
+    let file = ElfFile::parse(data).unwrap();
+    let packed = file
+        .relocations()
+        .unwrap()
+        .filter(|relocation| relocation.table == RelocationTable::AndroidRela)
+        .count();
+    assert!(packed <= 0x13);
+}
+
+#[test]
+fn aps2_count_too_large() {
+    let mut data = APS2.to_vec();
+    assert_eq!(data[APS2_TABLE..APS2_TABLE + 6], *b"APS2\x13\x00");
+    // -1 as a SLEB128, i.e. `u64::MAX` relocations.
+    data[APS2_TABLE + 4..APS2_TABLE + 6].copy_from_slice(&[0xff, 0x7f]);
+    let file = ElfFile::parse(data).unwrap();
+    assert!(matches!(file.relocations(), Err(Error::InvalidElf(_))));
+}
+
+#[test]
+fn relr_out_of_address_space() {
+    let mut data = RELR.to_vec();
+    let ptrs = ElfFile::parse(data.clone())
+        .unwrap()
+        .find_symbol("ptrs")
+        .unwrap()
+        .value;
+    assert_eq!(data[RELR_TABLE..RELR_TABLE + 8], ptrs.to_le_bytes());
+    // An address entry at the very end of the address space, followed by its bitmap.
+    data[RELR_TABLE..RELR_TABLE + 8].copy_from_slice(&(u64::MAX - 7).to_le_bytes());
+    let file = ElfFile::parse(data).unwrap();
+    assert!(matches!(file.relocations(), Err(Error::InvalidElf(_))));
+}
@@ -0,0 +1,26 @@
+//! Reads the symbol tables of the modules in `tests/fixtures` (see `build.sh`).
+
+use android_xdl::elf::ElfFile;
+
+const RELA: &[u8] = include_bytes!("fixtures/librela.so");
+const DEBUGDATA: &[u8] = include_bytes!("fixtures/libdebugdata.so");
+
+#[test]
+fn symtab() {
+    let file = ElfFile::parse(RELA.to_vec()).unwrap();
+    assert!(file.has_symtab());
+    assert!(file.gnu_debugdata().is_none());
+    let call = file
+        .symtab()
+        .into_iter()
+        .find(|symbol| symbol.name == "call");
+    assert!(call.is_some_and(|symbol| symbol.defined && symbol.is_function()));
+}
+
+#[test]
+fn gnu_debugdata() {
+    let file = ElfFile::parse(DEBUGDATA.to_vec()).unwrap();
+    assert!(!file.has_symtab());
+    let debugdata = file.gnu_debugdata().unwrap();
+    assert!(debugdata.starts_with(b"\xfd7zXZ\0"));
+}